@@ -0,0 +1,205 @@
+//! Portable settings bundles.
+//!
+//! A bundle holds the configuration file, to move a setup to another machine.
+//! Paths under the home directory are written as `~/...` on export, and
+//! expanded for the current user on import.
+//!
+//! Secrets saved in the system keyring aren't included, and must be saved again
+//! on the other machine.
+
+use crate::profile;
+use crate::yaml::{self, get, set};
+use crate::Config;
+use figment::Profile;
+use homedir::HomeDirExt;
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Component, Path, PathBuf};
+use writable::PathExt;
+
+/// Bundle file layout.
+#[derive(Debug, Serialize, Deserialize)]
+struct BundleFile {
+    /// Bundle format version.
+    memospot_bundle: u32,
+    /// Configuration file contents.
+    config: String,
+}
+
+/// Settings to move to another machine.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bundle {
+    /// Configuration file contents.
+    pub config: String,
+}
+
+/// Write a path under the home directory as `~/...`, with forward slashes,
+/// so it can be expanded on any platform.
+fn portable(path: &Path) -> PathBuf {
+    let collapsed = path.collapse_home();
+    if !collapsed.starts_with("~") {
+        return collapsed;
+    }
+    let components: Vec<String> = collapsed
+        .components()
+        .map(|component| match component {
+            Component::Normal(name) => name.to_string_lossy().to_string(),
+            component => component.as_os_str().to_string_lossy().to_string(),
+        })
+        .collect();
+    PathBuf::from(components.join("/"))
+}
+
+/// Paths of the portable settings in a configuration document, at the top level
+/// and in every profile section.
+fn path_settings(doc: &Value) -> Vec<Vec<String>> {
+    let mut roots: Vec<Vec<String>> = Vec::from([Vec::new()]);
+    roots.extend(
+        profile::sections(doc)
+            .into_iter()
+            .map(|key| Vec::from([key])),
+    );
+    roots
+        .iter()
+        .flat_map(|root| {
+            Bundle::PORTABLE_PATHS.iter().map(move |key| {
+                let mut path = root.clone();
+                path.extend(key.split('.').map(str::to_string));
+                path
+            })
+        })
+        .collect()
+}
+
+/// Rewrite the portable paths of configuration file contents, keeping comments if possible.
+fn rewrite_paths(contents: &str, rewrite: impl Fn(&Path) -> PathBuf) -> Result<String> {
+    let doc: Value =
+        serde_yaml::from_str(contents).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+    let mut new_doc = doc.clone();
+    for path in path_settings(&doc) {
+        if let Some(Value::String(value)) = get(&doc, &path) {
+            let rewritten = rewrite(Path::new(value)).to_string_lossy().to_string();
+            set(&mut new_doc, &path, Value::String(rewritten));
+        }
+    }
+    if new_doc == doc {
+        return Ok(contents.to_string());
+    }
+
+    match yaml::update(contents, &doc, &new_doc) {
+        Some(updated)
+            if serde_yaml::from_str::<Value>(&updated)
+                .is_ok_and(|parsed| parsed == new_doc) =>
+        {
+            Ok(updated)
+        }
+        _ => {
+            let yaml = serde_yaml::to_string(&new_doc)
+                .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+            Ok(Config::CONFIG_HEADER.to_string() + &yaml)
+        }
+    }
+}
+
+impl Bundle {
+    /// Current bundle format version.
+    ///
+    /// Version 1 bundles also held the logging configuration file, which is
+    /// ignored, as logging is now configured by `memospot.log`.
+    pub const VERSION: u32 = 2;
+
+    /// Settings holding paths, rewritten on export and import.
+    pub const PORTABLE_PATHS: &'static [&'static str] = &[
+        "memos.data",
+        "memos.binary_path",
+        "memos.working_dir",
+        "memospot.backups.path",
+        "memospot.log.file",
+    ];
+
+    /// Bundle the configuration file.
+    pub fn export(cfg_path: &Path) -> Result<String> {
+        let contents = fs::read_to_string(cfg_path)?;
+        let file = BundleFile {
+            memospot_bundle: Self::VERSION,
+            config: rewrite_paths(&contents, portable)?,
+        };
+        let yaml =
+            serde_yaml::to_string(&file).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        Ok("# Memospot settings bundle.\n".to_string() + &yaml)
+    }
+
+    /// Read a bundle, expanding its paths for the current user.
+    ///
+    /// Fails if the bundled configuration isn't valid.
+    pub fn import(contents: &str) -> Result<Bundle> {
+        let file: BundleFile = serde_yaml::from_str(contents)
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        if file.memospot_bundle > Self::VERSION {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "bundle version {} is not supported, please update Memospot",
+                    file.memospot_bundle
+                ),
+            ));
+        }
+
+        let config = rewrite_paths(&file.config, |path| {
+            path.expand_home().unwrap_or_else(|_| path.to_path_buf())
+        })?;
+        let Some(parsed) = Config::parse_contents(&config, &Profile::Default) else {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "bundled configuration is malformed",
+            ));
+        };
+        if let Some(error) = parsed.validate().first() {
+            return Err(Error::new(ErrorKind::InvalidData, error.to_string()));
+        }
+
+        Ok(Bundle { config })
+    }
+
+    /// Check that the bundled paths are usable on this machine.
+    ///
+    /// Directories must be writable, or be creatable in a writable directory,
+    /// and the Memos binary must exist. Relative paths aren't checked.
+    /// Returns a description of each problem.
+    pub fn check_paths(&self) -> Vec<String> {
+        let Ok(doc) = serde_yaml::from_str::<Value>(&self.config) else {
+            return Vec::new();
+        };
+        let mut problems: Vec<String> = Vec::new();
+        for path in path_settings(&doc) {
+            let Some(Value::String(value)) = get(&doc, &path) else {
+                continue;
+            };
+            let setting = Path::new(value);
+            if value.is_empty() || !setting.is_absolute() {
+                continue;
+            }
+            let key = path.join(".");
+            if key.ends_with("binary_path") {
+                if !setting.is_file() {
+                    problems.push(format!("`{}`: `{}` does not exist", key, value));
+                }
+                continue;
+            }
+            let existing = setting.ancestors().find(|ancestor| ancestor.exists());
+            if !existing.is_some_and(|existing| existing.is_dir() && existing.is_writable()) {
+                problems.push(format!("`{}`: `{}` is not writable", key, value));
+            }
+        }
+        problems
+    }
+
+    /// Save the bundled configuration, replacing the current file.
+    ///
+    /// The configuration file is backed up first.
+    pub fn save(&self, cfg_path: &Path) -> Result<()> {
+        Config::write_file(cfg_path, &self.config)
+    }
+}
@@ -5,12 +5,17 @@
 
 use crate::log::Log;
 use crate::memos::Memos;
-use crate::memospot::{Backups, Memospot, Migrations, RemoteServer, Updater, Window};
+use crate::memospot::{
+    Backups, Memospot, Migrations, Network, Proxy, RemoteServer, RemoteTls, Updater, Window,
+    Workspaces,
+};
 use crate::Config;
 
 impl Default for Config {
     fn default() -> Config {
         Config {
+            version: Some(Config::VERSION),
+            profiles: None,
             memos: Memos {
                 binary_path: None,
                 working_dir: None,
@@ -27,13 +32,36 @@ impl Default for Config {
                 },
                 log: Log {
                     enabled: Some(false),
+                    level: Some("info".to_string()),
+                    targets: None,
+                    file: None,
+                    rotate_size: Some(10),
+                    rotate_count: Some(5),
+                    console: Some(false),
+                    format: Some("text".to_string()),
                 },
                 migrations: Migrations {
                     enabled: Some(true),
                 },
+                network: Network {
+                    proxy: Proxy {
+                        url: None,
+                        no_proxy: None,
+                        username: None,
+                        password: None,
+                    },
+                },
                 remote: RemoteServer {
                     enabled: Some(false),
                     url: None,
+                    access_token: None,
+                    tls: RemoteTls {
+                        ca_bundle: None,
+                        fingerprint: None,
+                    },
+                    default: None,
+                    profiles: None,
+                    fallback: Some("error".to_string()),
                 },
                 updater: Updater {
                     enabled: Some(true),
@@ -48,6 +76,10 @@ impl Default for Config {
                     x: Some(0),
                     y: Some(0),
                 },
+                workspaces: Workspaces {
+                    active: None,
+                    list: None,
+                },
             },
         }
     }
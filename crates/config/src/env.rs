@@ -0,0 +1,182 @@
+//! Environment variable overrides.
+//!
+//! Every setting can be overridden with a variable named after its key, in
+//! uppercase, with the `MEMOSPOT_` prefix and nested keys separated by a double
+//! underscore. For instance:
+//! - `MEMOSPOT_MEMOS__MODE=demo` sets `memos.mode`.
+//! - `MEMOSPOT_MEMOSPOT__WINDOW__WIDTH=1280` sets `memospot.window.width`.
+//! - `MEMOSPOT_MEMOS__ENV__MEMOS_METRIC=false` adds `MEMOS_METRIC` to `memos.env`.
+//!
+//! Keys are case-insensitive and read in lowercase. Sequences are written
+//! in brackets, such as `[1, 2]`.
+//!
+//! `MEMOSPOT_PROFILE` selects the configuration profile instead.
+
+use crate::profile::merge_dict;
+use crate::Config;
+use figment::value::{Dict, Map};
+use figment::{Error, Metadata, Profile, Provider};
+use serde_json::Value;
+use std::io::Result;
+
+/// Prefix of environment variables overriding settings.
+pub const ENV_PREFIX: &str = "MEMOSPOT_";
+/// Separator between nested keys in environment variable names.
+pub const ENV_SEPARATOR: &str = "__";
+/// Variables with the prefix that don't override settings, without the prefix.
+pub(crate) const ENV_RESERVED: &[&str] = &["PROFILE"];
+
+/// An environment variable overriding a setting.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvVar {
+    /// Variable name, such as `MEMOSPOT_MEMOS__MODE`.
+    ///
+    /// Maps end with `*`, to be replaced with the map key.
+    pub name: String,
+    /// Dotted setting key, such as `memos.mode`.
+    pub key: String,
+    /// Value type, such as `string` or `integer`.
+    pub kind: String,
+    /// First paragraph of the setting documentation.
+    pub description: String,
+}
+
+/// Variables of the process environment, leaving out those that aren't valid Unicode.
+pub(crate) fn process_vars() -> Vec<(String, String)> {
+    std::env::vars_os()
+        .filter_map(|(name, value)| Some((name.into_string().ok()?, value.into_string().ok()?)))
+        .collect()
+}
+
+/// Value of a variable, with a case-insensitive name.
+pub(crate) fn var(vars: &[(String, String)], name: &str) -> Option<String> {
+    vars.iter()
+        .find(|(var, _)| var.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.clone())
+}
+
+/// Settings overridden by `MEMOSPOT_*` environment variables.
+///
+/// Reads the given variables instead of the process environment, so they can
+/// be set without affecting anything else, such as in tests.
+pub(crate) struct EnvOverrides(pub(crate) Vec<(String, String)>);
+
+impl Provider for EnvOverrides {
+    fn metadata(&self) -> Metadata {
+        Metadata::named(format!("`{}` environment variable(s)", ENV_PREFIX))
+    }
+
+    fn data(&self) -> std::result::Result<Map<Profile, Dict>, Error> {
+        let mut dict = Dict::new();
+        for (name, value) in &self.0 {
+            let Some(key) = name
+                .get(..ENV_PREFIX.len())
+                .filter(|prefix| prefix.eq_ignore_ascii_case(ENV_PREFIX))
+                .map(|_| name[ENV_PREFIX.len()..].trim())
+            else {
+                continue;
+            };
+            if key.is_empty() || ENV_RESERVED.iter().any(|r| r.eq_ignore_ascii_case(key)) {
+                continue;
+            }
+            let key = key.to_lowercase().replace(ENV_SEPARATOR, ".");
+            let value = value.parse().expect("infallible");
+            if let Some(nested) = figment::util::nest(&key, value).into_dict() {
+                merge_dict(&mut dict, nested);
+            }
+        }
+        Ok(Profile::Global.collect(dict))
+    }
+}
+
+/// Environment variable name for a setting path.
+pub(crate) fn env_name(path: &[String]) -> String {
+    let key: Vec<String> = path.iter().map(|segment| segment.to_uppercase()).collect();
+    ENV_PREFIX.to_string() + &key.join(ENV_SEPARATOR)
+}
+
+/// Collect the settings of a schema, recursively.
+fn collect(schema: &Value, path: &mut Vec<String>, vars: &mut Vec<EnvVar>) {
+    if let Some(Value::Object(properties)) = schema.get("properties") {
+        for (key, property) in properties {
+            path.push(key.clone());
+            collect(property, path, vars);
+            path.pop();
+        }
+        return;
+    }
+
+    let kinds: Vec<&str> = match schema.get("type") {
+        Some(Value::String(kind)) => Vec::from([kind.as_str()]),
+        Some(Value::Array(kinds)) => kinds
+            .iter()
+            .filter_map(Value::as_str)
+            .filter(|kind| *kind != "null")
+            .collect(),
+        _ => Vec::new(),
+    };
+    let is_map = kinds == ["object"] && schema.get("additionalProperties").is_some();
+    let description = schema
+        .get("description")
+        .and_then(Value::as_str)
+        .and_then(|description| description.split("\n\n").next())
+        .unwrap_or_default();
+
+    let mut path = path.clone();
+    let kind = if is_map {
+        path.push("*".to_string());
+        schema
+            .get("additionalProperties")
+            .and_then(|values| values.get("type"))
+            .and_then(Value::as_str)
+            .unwrap_or("string")
+            .to_string()
+    } else {
+        kinds.join(" or ")
+    };
+    vars.push(EnvVar {
+        name: env_name(&path),
+        key: path.join("."),
+        kind,
+        description: description.to_string(),
+    });
+}
+
+impl Config {
+    /// List the environment variables that override settings, derived from the configuration types.
+    pub fn env_vars() -> Result<Vec<EnvVar>> {
+        let mut vars: Vec<EnvVar> = Vec::new();
+        collect(&Self::schema_value()?, &mut Vec::new(), &mut vars);
+        Ok(vars)
+    }
+
+    /// Filter the `MEMOSPOT_*` variable names that don't match any setting.
+    ///
+    /// Variables setting a whole section at once, such as `MEMOSPOT_MEMOS`, are accepted.
+    pub fn unknown_env_vars(names: impl IntoIterator<Item = String>) -> Vec<String> {
+        let known = Self::env_vars().unwrap_or_default();
+        names
+            .into_iter()
+            .filter(|name| {
+                let Some(key) = name
+                    .to_uppercase()
+                    .strip_prefix(ENV_PREFIX)
+                    .map(str::to_string)
+                else {
+                    return false;
+                };
+                if ENV_RESERVED.contains(&key.as_str()) {
+                    return false;
+                }
+                let key = key.to_lowercase().replace(ENV_SEPARATOR, ".");
+                !known.iter().any(|var| {
+                    let in_map = var
+                        .key
+                        .strip_suffix('*')
+                        .is_some_and(|map| key.starts_with(map));
+                    in_map || var.key == key || var.key.starts_with(&format!("{}.", key))
+                })
+            })
+            .collect()
+    }
+}
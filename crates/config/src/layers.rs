@@ -0,0 +1,276 @@
+//! Layered configuration files.
+//!
+//! Besides the user file, administrators may ship a system-wide file with
+//! defaults for every user, and a policy file with settings users can't change.
+//! From lowest to highest precedence, settings come from:
+//! 1. Built-in defaults.
+//! 2. The system file.
+//! 3. The user file.
+//! 4. `MEMOSPOT_*` environment variables, see [`crate::env`].
+//! 5. The policy file.
+//!
+//! Each file may have profile sections, see [`crate::profile`].
+
+use crate::env::{self, EnvOverrides};
+use crate::profile::{self, Sections};
+use crate::upgrade::UpgradedFile;
+use crate::yaml::{self, get, insert, leaves, remove};
+use crate::Config;
+use figment::providers::{Format, Serialized, Yaml};
+use figment::{Figment, Profile};
+use serde_yaml::Value;
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+
+/// Profile selected when none is given.
+#[cfg(debug_assertions)]
+const DEFAULT_PROFILE: &str = "debug";
+#[cfg(not(debug_assertions))]
+const DEFAULT_PROFILE: &str = "release";
+
+/// Remove a key, along with parent mappings left empty.
+fn prune(root: &mut Value, path: &[String]) {
+    remove(root, path);
+    for depth in (1..path.len()).rev() {
+        match get(root, &path[..depth]) {
+            Some(Value::Mapping(map)) if map.is_empty() => remove(root, &path[..depth]),
+            _ => break,
+        };
+    }
+}
+
+/// Configuration files, by layer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigLayers {
+    /// System-wide defaults, overridden by the user file.
+    pub system: Option<PathBuf>,
+    /// User configuration file. The only file Memospot writes to.
+    pub user: PathBuf,
+    /// Locked settings, overriding every other source.
+    pub policy: Option<PathBuf>,
+    /// Selected profile. Defaults to `MEMOSPOT_PROFILE`, then the build profile.
+    pub profile: Option<String>,
+    /// Environment variables to read instead of the process environment.
+    pub env: Option<Vec<(String, String)>>,
+}
+
+impl From<&Path> for ConfigLayers {
+    /// A single user file, without system or policy layers.
+    fn from(user: &Path) -> Self {
+        Self {
+            system: None,
+            user: user.to_path_buf(),
+            policy: None,
+            profile: None,
+            env: None,
+        }
+    }
+}
+
+impl ConfigLayers {
+    /// File name of the system-wide defaults.
+    pub const SYSTEM_FILE: &'static str = "memospot.yaml";
+    /// File name of the locked settings.
+    pub const POLICY_FILE: &'static str = "policy.yaml";
+
+    /// Directory where administrators place the system and policy files.
+    ///
+    /// - Windows: `%ProgramData%\memospot`
+    /// - macOS: `/Library/Application Support/memospot`
+    /// - Other platforms: `/etc/memospot`
+    pub fn system_dir() -> PathBuf {
+        #[cfg(windows)]
+        let base = std::env::var_os("ProgramData")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from(r"C:\ProgramData"));
+        #[cfg(target_os = "macos")]
+        let base = PathBuf::from("/Library/Application Support");
+        #[cfg(not(any(windows, target_os = "macos")))]
+        let base = PathBuf::from("/etc");
+
+        base.join("memospot")
+    }
+
+    /// Layers for a user file, with the system and policy files found in `system_dir`.
+    pub fn discover(user: &Path, system_dir: &Path) -> Self {
+        let existing = |name: &str| Some(system_dir.join(name)).filter(|p| p.is_file());
+        Self {
+            system: existing(Self::SYSTEM_FILE),
+            user: user.to_path_buf(),
+            policy: existing(Self::POLICY_FILE),
+            profile: None,
+            env: None,
+        }
+    }
+
+    /// Whether settings come from other files besides the user file.
+    pub fn is_layered(&self) -> bool {
+        self.system.is_some() || self.policy.is_some()
+    }
+
+    /// Environment variables overriding settings.
+    fn env_vars(&self) -> Vec<(String, String)> {
+        self.env.clone().unwrap_or_else(env::process_vars)
+    }
+
+    /// Profile whose sections apply.
+    pub fn selected_profile(&self) -> Profile {
+        if let Some(profile) = self.profile.as_deref().map(str::trim) {
+            if !profile.is_empty() {
+                return Profile::new(profile);
+            }
+        }
+        let name = format!("{}PROFILE", env::ENV_PREFIX);
+        match env::var(&self.env_vars(), &name) {
+            Some(profile) if !profile.trim().is_empty() => Profile::new(profile.trim()),
+            _ => Profile::new(DEFAULT_PROFILE),
+        }
+    }
+
+    /// Every configuration source, in order of precedence.
+    ///
+    /// Environment variables and the policy file override every profile.
+    pub(crate) fn figment(&self) -> Figment {
+        let mut figment = Figment::new();
+        if let Some(system) = &self.system {
+            figment = figment.merge(Sections::new(Yaml::file(system)));
+        }
+        figment = figment
+            .merge(Sections::new(UpgradedFile(self.user.clone())))
+            .merge(EnvOverrides(self.env_vars()));
+        if let Some(policy) = &self.policy {
+            figment = figment.merge(Sections::new(Yaml::file(policy)).base(Profile::Global));
+        }
+        figment
+            .select(self.selected_profile())
+            .join(Serialized::defaults(Config::default()))
+    }
+
+    /// Load the configuration from every layer.
+    ///
+    /// The user file is upgraded in memory, but never rewritten.
+    pub fn extract(&self) -> Result<Config> {
+        self.figment()
+            .extract::<Config>()
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e))
+    }
+
+    /// Settings the user file builds upon: the defaults and the system file.
+    fn base(&self) -> Result<Config> {
+        let mut figment = Figment::new();
+        if let Some(system) = &self.system {
+            figment = figment.merge(Sections::new(Yaml::file(system)));
+        }
+        figment
+            .select(self.selected_profile())
+            .join(Serialized::defaults(Config::default()))
+            .extract::<Config>()
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e))
+    }
+
+    /// Dotted keys locked by the policy file, for the selected profile.
+    pub fn locked_keys(&self) -> Vec<String> {
+        let Some(policy) = &self.policy else {
+            return Vec::new();
+        };
+        fs::read_to_string(policy)
+            .ok()
+            .and_then(|contents| serde_yaml::from_str::<Value>(&contents).ok())
+            .filter(Value::is_mapping)
+            .map(|value| profile::flatten(&value, &self.selected_profile()).0)
+            .map(|value| leaves(&value).iter().map(|path| path.join(".")).collect())
+            .unwrap_or_default()
+    }
+
+    /// Reset the user file to defaults, discarding any comments.
+    ///
+    /// With system or policy layers, the new file only holds the version,
+    /// so the settings from the lower layers apply.
+    pub fn reset(&self) -> Result<()> {
+        if !self.is_layered() {
+            return Config::reset_file(&self.user);
+        }
+        let contents = format!("{}version: {}\n", Config::CONFIG_HEADER, Config::VERSION);
+        Config::write_file(&self.user, &contents)
+    }
+
+    /// Save the configuration to the user file.
+    ///
+    /// Settings are written to the section of the selected profile when they're set there.
+    /// With system or policy layers, only the settings that differ from the
+    /// lower layers are written, plus those already in the user file.
+    /// Locked settings are never written.
+    pub fn save(&self, config: &Config) -> Result<()> {
+        let profile = self.selected_profile();
+        if !self.is_layered() {
+            return Config::save_profile(&self.user, config, &profile);
+        }
+
+        let to_value = |config: &Config| {
+            serde_yaml::to_value(config).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+        };
+        let new = to_value(config)?;
+        let contents =
+            Config::contents_to_edit(fs::read_to_string(&self.user).unwrap_or_default());
+        let current: Value = serde_yaml::from_str(&contents).unwrap_or(Value::Null);
+
+        let mut user = match &current {
+            Value::Mapping(_) => current.clone(),
+            _ => Value::Mapping(Default::default()),
+        };
+        // Keys in the user file follow the configuration, unless shadowed by
+        // another section. Unknown keys are kept.
+        if current.is_mapping() {
+            for leaf in leaves(&current) {
+                let Some(path) = profile::config_path(&current, &profile, &leaf) else {
+                    continue;
+                };
+                if profile::write_path(&current, &profile, &path) != leaf {
+                    continue;
+                }
+                if let Some(value) = get(&new, &path) {
+                    insert(&mut user, &leaf, value.clone());
+                }
+            }
+        }
+        // Keys that differ from the lower layers are added.
+        let mut keys = self.base()?.diff(config);
+        keys.push("version".to_string());
+        for key in keys {
+            let path: Vec<String> = key.split('.').map(str::to_string).collect();
+            if let Some(value) = get(&new, &path) {
+                let target = profile::write_path(&user, &profile, &path);
+                insert(&mut user, &target, value.clone());
+            }
+        }
+        // Locked keys are removed from the top level and from every section.
+        let sections = profile::sections(&user);
+        for key in self.locked_keys() {
+            let path: Vec<String> = key.split('.').map(str::to_string).collect();
+            prune(&mut user, &path);
+            for section in &sections {
+                prune(
+                    &mut user,
+                    &[Vec::from([section.clone()]), path.clone()].concat(),
+                );
+            }
+        }
+
+        // Edit the existing file in place if possible, so comments are kept.
+        let file_contents = match yaml::update(&contents, &current, &user) {
+            Some(updated)
+                if serde_yaml::from_str::<Value>(&updated)
+                    .is_ok_and(|parsed| parsed == user) =>
+            {
+                updated
+            }
+            _ => {
+                let yaml = serde_yaml::to_string(&user)
+                    .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+                Config::CONFIG_HEADER.to_string() + &yaml
+            }
+        };
+        Config::write_file(&self.user, &file_contents)
+    }
+}
@@ -2,96 +2,279 @@
 
 mod tests;
 
+mod bundle;
 pub mod default;
+pub mod env;
+mod layers;
 mod log;
 mod memos;
 mod memospot;
+mod merge;
+mod origin;
+mod profile;
+mod recover;
+mod schema;
+pub mod secret;
+mod upgrade;
+mod validate;
+mod yaml;
 
 use crate::memos::Memos;
-use crate::memospot::Memospot;
+use crate::memospot::{Memospot, Workspace};
+use crate::profile::Sections;
 
-use figment::providers::{Env, Format, Serialized, Yaml};
+pub use crate::bundle::Bundle;
+pub use crate::env::EnvVar;
+pub use crate::layers::ConfigLayers;
+pub use crate::log::Log;
+pub use crate::memospot::{Proxy, RemoteProfile, RemoteServer, RemoteTls, Window};
+pub use crate::origin::{Layer, Origin};
+pub use crate::recover::Recovery;
+pub use crate::secret::SecretRef;
+pub use crate::validate::ValidationError;
+
+use figment::providers::{Format, Serialized, Yaml};
 use figment::{Figment, Profile};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
 
-use std::io::{Error, ErrorKind, Result};
-use std::path::Path;
+use std::fs::File;
+use std::io::{Error, ErrorKind, Result, Write};
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 use std::{fs, thread};
 
-#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct Config {
+    /// Configuration version, used to upgrade files written by older releases.
+    pub version: Option<u32>,
+    /// Profiles with a section in this file, besides `default`, `global`, `debug`
+    /// and `release`. Other top-level mappings are not profile sections.
+    pub profiles: Option<Vec<String>>,
     pub memos: Memos,
     pub memospot: Memospot,
 }
 
 impl Config {
-    const CONFIG_HEADER: &'static str = r#"#
-#! User comments on this file will be lost whenever
-#    the configuration is updated by Memospot !
+    const CONFIG_HEADER: &'static str = r#"# yaml-language-server: $schema=https://raw.githubusercontent.com/memospot/memospot/main/crates/config/memospot.schema.json
 #
 #   For a explained configuration file, see:
 # https://memospot.github.io/configuration.html
 #
 "#;
 
+    /// Configuration as YAML, for display. Secret values are redacted.
     pub fn to_string(&self) -> Result<String> {
-        serde_yaml::to_string(&self).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+        serde_yaml::to_string(&self.redacted())
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e))
     }
 
+    /// Load a configuration file, without system or policy layers.
+    ///
+    /// See [`ConfigLayers::extract`].
     pub fn init(cfg_path: &Path) -> Result<Config> {
-        #[cfg(debug_assertions)]
-        const DEFAULT_PROFILE: &str = "debug";
-        #[cfg(not(debug_assertions))]
-        const DEFAULT_PROFILE: &str = "release";
+        ConfigLayers::from(cfg_path).extract()
+    }
 
-        let default_config = Config::default();
+    /// Upgrade a configuration file written by an older Memospot release.
+    ///
+    /// See [`ConfigLayers::upgrade`].
+    pub fn upgrade_file(cfg_path: &Path) -> Result<bool> {
+        ConfigLayers::from(cfg_path).upgrade()
+    }
 
-        // This the base figment, without our `Config` defaults.
-        let figment = Figment::new()
-            .merge(Yaml::file(cfg_path.to_str().unwrap_or_default()))
-            .merge(Env::prefixed("MEMOSPOT_"))
-            .select(Profile::from_env_or("MEMOSPOT_PROFILE", DEFAULT_PROFILE))
-            .join(Serialized::defaults(default_config));
+    /// Rewrite a configuration file with the current version.
+    ///
+    /// The previous version is kept as a backup.
+    /// Missing and malformed files are left untouched.
+    fn write_upgrade(cfg_path: &Path) -> Result<bool> {
+        let Ok(contents) = fs::read_to_string(cfg_path) else {
+            return Ok(false);
+        };
+        let Some(upgraded) = Self::upgrade_contents(&contents)? else {
+            return Ok(false);
+        };
+        Self::write_file(cfg_path, &upgraded)?;
+        Ok(true)
+    }
 
-        figment
-            .extract::<Config>()
-            .map_err(|e| Error::new(ErrorKind::InvalidData, e))
+    /// Upgrade configuration file contents, rewriting only the changed keys.
+    ///
+    /// Returns None if the contents are malformed or already up to date.
+    fn upgrade_contents(contents: &str) -> Result<Option<String>> {
+        let Ok(old) = serde_yaml::from_str::<Value>(contents) else {
+            return Ok(None);
+        };
+        let mut new = old.clone();
+        if !Config::upgrade(&mut new)? {
+            return Ok(None);
+        }
+
+        // Add a missing version key at the top, right after the header comments.
+        let mut base = old.clone();
+        let mut lines: Vec<&str> = contents.lines().collect();
+        let version_line = format!("version: {}", Config::VERSION);
+        if let Value::Mapping(map) = &mut base {
+            if !map.contains_key("version") {
+                let position = lines
+                    .iter()
+                    .position(|l| !l.trim().is_empty() && !l.trim_start().starts_with('#'))
+                    .unwrap_or(lines.len());
+                lines.insert(position, &version_line);
+                map.insert(Value::String("version".to_string()), new["version"].clone());
+            }
+        }
+
+        let upgraded = match yaml::update(&lines.join("\n"), &base, &new) {
+            Some(updated)
+                if serde_yaml::from_str::<Value>(&updated).is_ok_and(|v| v == new) =>
+            {
+                updated
+            }
+            _ => {
+                let yaml = serde_yaml::to_string(&new)
+                    .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+                Self::CONFIG_HEADER.to_string() + &yaml
+            }
+        };
+        Ok(Some(upgraded))
+    }
+
+    /// Contents to edit when saving: upgraded first, if written by an older release.
+    pub(crate) fn contents_to_edit(contents: String) -> String {
+        Self::upgrade_contents(&contents)
+            .ok()
+            .flatten()
+            .unwrap_or(contents)
     }
 
-    /// Parse configuration file.
+    /// Parse configuration file, for the selected profile.
     pub fn parse_file(cfg_path: &Path) -> Result<Config> {
         Figment::new()
-            .merge(Yaml::file(cfg_path.to_str().unwrap_or_default()))
+            .merge(Sections::new(Yaml::file(cfg_path)))
+            .select(ConfigLayers::from(cfg_path).selected_profile())
             .extract::<Config>()
             .map_err(|e| Error::new(ErrorKind::InvalidData, e))
     }
 
-    /// Save configuration to supplied file.
-    pub fn save_file(cfg_path: &Path, config: &Config) -> Result<()> {
-        if cfg_path.is_dir() {
-            return Err(Error::new(
-                ErrorKind::InvalidInput,
-                "provided path is a directory",
-            ));
-        }
-
+    /// Render a new configuration file.
+    fn to_contents(config: &Config) -> Result<String> {
         let Ok(yaml) = serde_yaml::to_string(&config) else {
             return Err(Error::new(
                 ErrorKind::InvalidData,
                 "failed to serialize configuration",
             ));
         };
+        Ok(Self::CONFIG_HEADER.to_string() + &yaml)
+    }
+
+    /// Parse configuration file contents for a profile, filling missing settings with defaults.
+    fn parse_contents(contents: &str, profile: &Profile) -> Option<Config> {
+        Figment::new()
+            .merge(Sections::new(Yaml::string(contents)))
+            .select(profile.clone())
+            .join(Serialized::defaults(Config::default()))
+            .extract::<Config>()
+            .ok()
+    }
+
+    /// Path next to the configuration file, with a suffix appended to its name.
+    pub(crate) fn sibling_path(cfg_path: &Path, suffix: &str) -> PathBuf {
+        let mut file_name = cfg_path.file_name().unwrap_or_default().to_os_string();
+        file_name.push(suffix);
+        cfg_path.with_file_name(file_name)
+    }
+
+    /// Path to the backup of the previous configuration file version.
+    pub fn backup_path(cfg_path: &Path) -> PathBuf {
+        Self::sibling_path(cfg_path, ".bak")
+    }
+
+    /// Update an existing configuration file, keeping comments, ordering and unknown keys.
+    ///
+    /// Only settings that differ from `current` are rewritten, in the section of
+    /// `profile` if they're set there. Falls back to rendering a new file if
+    /// `current` can't be parsed or edited in place.
+    fn update_contents(current: &str, config: &Config, profile: &Profile) -> Result<String> {
+        let (Ok(doc), Some(current_config), Ok(new)) = (
+            serde_yaml::from_str::<Value>(current),
+            Self::parse_contents(current, profile),
+            serde_yaml::to_value(config),
+        ) else {
+            return Self::to_contents(config);
+        };
+
+        let mut new_doc = match &doc {
+            Value::Mapping(_) => doc.clone(),
+            _ => Value::Mapping(Default::default()),
+        };
+        for key in current_config.diff(config) {
+            let path: Vec<String> = key.split('.').map(str::to_string).collect();
+            if let Some(value) = yaml::get(&new, &path) {
+                let target = profile::write_path(&new_doc, profile, &path);
+                yaml::insert(&mut new_doc, &target, value.clone());
+            }
+        }
+
+        // Make sure the edited document is read back exactly as the new configuration.
+        let is_exact =
+            |contents: &str| Self::parse_contents(contents, profile).as_ref() == Some(config);
+        if let Some(updated) = yaml::update(current, &doc, &new_doc).filter(|u| is_exact(u)) {
+            return Ok(updated);
+        }
+        // Keep profile sections, even if comments are lost.
+        if !profile::sections(&doc).is_empty() {
+            if let Ok(yaml) = serde_yaml::to_string(&new_doc) {
+                let rendered = Self::CONFIG_HEADER.to_string() + &yaml;
+                if is_exact(&rendered) {
+                    return Ok(rendered);
+                }
+            }
+        }
+        Self::to_contents(config)
+    }
+
+    /// Save configuration to supplied file, for the selected profile.
+    ///
+    /// Existing files are edited in place, so user comments are kept.
+    pub fn save_file(cfg_path: &Path, config: &Config) -> Result<()> {
+        let profile = ConfigLayers::from(cfg_path).selected_profile();
+        Self::save_profile(cfg_path, config, &profile)
+    }
+
+    /// Save configuration to supplied file, writing settings to the section of `profile`
+    /// when they're set there.
+    pub(crate) fn save_profile(
+        cfg_path: &Path,
+        config: &Config,
+        profile: &Profile,
+    ) -> Result<()> {
+        let file_contents = match fs::read_to_string(cfg_path) {
+            Ok(current) => {
+                Self::update_contents(&Self::contents_to_edit(current), config, profile)?
+            }
+            Err(_) => Self::to_contents(config)?,
+        };
+        Self::write_file(cfg_path, &file_contents)
+    }
 
-        let file_contents = Self::CONFIG_HEADER.to_string() + &yaml;
+    /// Write configuration file contents, retrying on failure.
+    fn write_file(cfg_path: &Path, file_contents: &str) -> Result<()> {
+        if cfg_path.is_dir() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "provided path is a directory",
+            ));
+        }
 
-        let mut last_error = Error::new(ErrorKind::Other, "unable to write configuration");
+        let mut last_error = Error::other("unable to write configuration");
         for retry in 0..10 {
             if retry > 0 {
                 thread::sleep(Duration::from_millis(100 * retry));
             }
 
-            if let Err(e) = fs::write(cfg_path, file_contents.clone()) {
+            if let Err(e) = Self::write_atomic(cfg_path, file_contents) {
                 last_error = e;
                 continue;
             };
@@ -101,9 +284,113 @@ impl Config {
         Err(last_error)
     }
 
-    /// Reset configuration file to defaults.
+    /// Replace the configuration file without ever leaving it partially written.
+    ///
+    /// Contents go to a temporary file in the same directory, which is flushed
+    /// to disk and then renamed over the configuration file. The previous version
+    /// is kept as a `.bak` file, unless it's malformed or fails validation.
+    fn write_atomic(cfg_path: &Path, file_contents: &str) -> Result<()> {
+        let tmp_path = Self::sibling_path(cfg_path, ".tmp");
+        let result = (|| -> Result<()> {
+            let mut file = File::create(&tmp_path)?;
+            file.write_all(file_contents.as_bytes())?;
+            file.sync_all()?;
+            drop(file);
+
+            if let Ok(current) = fs::read_to_string(cfg_path) {
+                let valid = Self::parse_contents(&current, &Profile::Default)
+                    .is_some_and(|c| c.validate().is_empty());
+                if current != file_contents && valid {
+                    fs::copy(cfg_path, Self::backup_path(cfg_path))?;
+                }
+            }
+
+            fs::rename(&tmp_path, cfg_path)?;
+
+            // Persist the rename itself.
+            #[cfg(unix)]
+            if let Some(Ok(dir)) = cfg_path.parent().map(File::open) {
+                dir.sync_all().ok();
+            }
+            Ok(())
+        })();
+
+        if result.is_err() {
+            fs::remove_file(&tmp_path).ok();
+        }
+        result
+    }
+
+    /// Restore the previous configuration file version from its backup.
+    ///
+    /// The current file is kept next to it, with a `.broken` suffix.
+    /// Fails if the backup is missing or invalid.
+    pub fn restore_backup(cfg_path: &Path) -> Result<()> {
+        let backup = Self::backup_path(cfg_path);
+        let contents = fs::read_to_string(&backup)?;
+        let valid = Self::parse_contents(&contents, &Profile::Default)
+            .is_some_and(|c| c.validate().is_empty());
+        if !valid {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("invalid backup file: {}", backup.to_string_lossy()),
+            ));
+        }
+
+        if cfg_path.is_file() {
+            fs::copy(cfg_path, Self::sibling_path(cfg_path, ".broken"))?;
+        }
+        Self::write_file(cfg_path, &contents)
+    }
+
+    /// Switch to a saved workspace.
+    ///
+    /// Settings of the active workspace (or "default", if none is active) are stored
+    /// in the workspace list, then replaced by the settings of the target workspace.
+    /// Unset target settings fall back to defaults.
+    pub fn switch_workspace(&mut self, name: &str) -> Result<()> {
+        let workspaces = &mut self.memospot.workspaces;
+        let current_name = workspaces
+            .active
+            .clone()
+            .filter(|n| !n.trim().is_empty())
+            .unwrap_or("default".to_string());
+        if current_name == name {
+            return Ok(());
+        }
+
+        let list = workspaces.list.get_or_insert_with(Vec::new);
+        let Some(target) = list.iter().find(|w| w.name == name).cloned() else {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                format!("workspace `{}` not found", name),
+            ));
+        };
+
+        let current = Workspace {
+            name: current_name,
+            data: self.memos.data.clone(),
+            mode: self.memos.mode.clone(),
+            port: self.memos.port,
+            backups: self.memospot.backups.path.clone(),
+        };
+        match list.iter_mut().find(|w| w.name == current.name) {
+            Some(saved) => *saved = current,
+            None => list.push(current),
+        }
+
+        let default_config = Config::default();
+        self.memos.data = target.data;
+        self.memos.mode = target.mode.or(default_config.memos.mode);
+        self.memos.port = target.port.or(default_config.memos.port);
+        self.memospot.backups.path = target.backups;
+        self.memospot.workspaces.active = Some(target.name);
+        Ok(())
+    }
+
+    /// Reset configuration file to defaults, discarding any comments.
     pub fn reset_file(cfg_path: &Path) -> Result<()> {
         let default_config = Config::default();
-        Config::save_file(cfg_path, &default_config)
+        Config::write_file(cfg_path, &Config::to_contents(&default_config)?)
     }
 }
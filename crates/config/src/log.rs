@@ -1,8 +1,44 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
-// https://github.com/estk/log4rs/blob/main/docs/Configuration.md
-
-#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
+/// Logging settings.
+///
+/// Memospot writes its log to a rotating file in its data directory.
+/// A separate `logging_config.yaml` file is no longer used.
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct Log {
+    /// Enable logging.
     pub enabled: Option<bool>,
+    /// Log level, for every target without its own level.
+    ///
+    /// Can be one of: trace, debug, info, warn, error, off.
+    pub level: Option<String>,
+    /// Log levels by target, overriding `level`.
+    ///
+    /// Targets include `memospot`, `migration` and `memos`, the output of the Memos server.
+    pub targets: Option<BTreeMap<String, String>>,
+    /// Log file path. Relative paths are relative to the Memospot data directory.
+    pub file: Option<String>,
+    /// Size of the log file, in megabytes, that triggers a rotation.
+    pub rotate_size: Option<u64>,
+    /// Number of rotated, compressed log files to keep. Must be at least 1.
+    pub rotate_count: Option<u32>,
+    /// Also log to the console.
+    pub console: Option<bool>,
+    /// Log line format.
+    ///
+    /// Can be one of: text, json. With `json`, each line is a JSON object with
+    /// the timestamp, level, target, message and structured fields, for use with `jq`.
+    pub format: Option<String>,
+}
+
+impl Log {
+    /// Valid log levels.
+    pub const LEVELS: &'static [&'static str] =
+        &["trace", "debug", "info", "warn", "error", "off"];
+    /// Valid log formats.
+    pub const FORMATS: &'static [&'static str] = &["text", "json"];
+    /// Default log file name, in the Memospot data directory.
+    pub const DEFAULT_FILE: &'static str = "memospot.log";
 }
@@ -1,10 +1,11 @@
 //! Memos configuration
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 /// Memos configuration.
-#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct Memos {
     /// Memos binary path.
     pub binary_path: Option<String>,
@@ -32,6 +33,9 @@ pub struct Memos {
     pub port: Option<u16>,
 
     /// Custom environment variables to pass to Memos.
+    ///
+    /// Values may reference secrets kept out of this file, with `secret:NAME`
+    /// for the system keyring or `file:PATH` for the contents of a file.
     pub env: Option<HashMap<String, String>>,
     // Memos server log settings.
     // pub log: Log,
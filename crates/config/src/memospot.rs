@@ -1,33 +1,162 @@
 use crate::log::Log;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::io::{Error, ErrorKind, Result};
 
-#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct Backups {
     /// Enable backups.
     pub enabled: Option<bool>,
     /// Directory where Memospot will store backups.
     pub path: Option<String>,
 }
-#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct Migrations {
     /// Enable database migrations.
     /// Can be disabled to use Memospot with an uncertified Memos version.
     pub enabled: Option<bool>,
 }
 
-#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct Proxy {
+    /// Proxy server URL, such as `http://proxy:3128` or `socks5://proxy:1080`.
+    ///
+    /// Supported schemes are `http`, `https`, `socks5` and `socks5h`.
+    pub url: Option<String>,
+    /// Comma-separated list of hosts that bypass the proxy.
+    ///
+    /// Local addresses always bypass the proxy.
+    pub no_proxy: Option<String>,
+    /// Proxy username.
+    pub username: Option<String>,
+    /// Proxy password.
+    ///
+    /// May be a reference to a secret, such as `secret:proxy` or `file:/run/secrets/proxy`.
+    pub password: Option<String>,
+}
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct Network {
+    /// Proxy settings, used by Memospot and handed to Memos.
+    pub proxy: Proxy,
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct RemoteTls {
+    /// Path to a PEM file with additional CA certificates to trust.
+    pub ca_bundle: Option<String>,
+    /// SHA-256 fingerprint of the server certificate, as hexadecimal.
+    ///
+    /// When set, the server certificate is accepted only if it matches,
+    /// even if it's self-signed. Colons and case are ignored.
+    pub fingerprint: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct RemoteProfile {
+    /// Profile name.
+    pub name: String,
+    /// Remote server URL.
+    pub url: String,
+    /// Memos access token, used to open the server already authenticated.
+    ///
+    /// Tokens are moved to the system keyring on startup and replaced by a reference
+    /// to the saved secret, such as `secret:remote.work`. May also be a reference to
+    /// another secret, such as `secret:work` or `file:/path/to/token`.
+    pub access_token: Option<String>,
+    /// TLS settings. Falls back to `memospot.remote.tls` if unset.
+    pub tls: Option<RemoteTls>,
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct RemoteServer {
     /// Enable remote server. This will disable spawning a local Memos server.
     pub enabled: Option<bool>,
+    /// Remote server URL. Used when no profile is selected.
     pub url: Option<String>,
+    /// Memos access token for `url`. See [`RemoteProfile::access_token`].
+    pub access_token: Option<String>,
+    /// TLS settings for `url` and profiles without their own settings.
+    pub tls: RemoteTls,
+    /// Name of the profile to use by default.
+    pub default: Option<String>,
+    /// Saved remote servers.
+    pub profiles: Option<Vec<RemoteProfile>>,
+    /// What to do if the remote server is unreachable at startup.
+    ///
+    /// Can be one of:
+    /// - error: show an error message.
+    /// - retry: ask whether to check again.
+    /// - local: fall back to the local Memos server.
+    pub fallback: Option<String>,
+}
+
+impl RemoteServer {
+    /// Reserved profile name to use the local Memos server.
+    pub const LOCAL_PROFILE: &'static str = "local";
+
+    /// Find a saved profile by name.
+    pub fn profile(&self, name: &str) -> Option<&RemoteProfile> {
+        self.profiles.as_ref()?.iter().find(|p| p.name == name)
+    }
+
+    /// Active remote profile.
+    ///
+    /// Returns `None` if the remote server is disabled or if `url` is in use.
+    pub fn active_profile(&self) -> Option<&RemoteProfile> {
+        if !self.enabled.unwrap_or_default() {
+            return None;
+        }
+        self.default.as_deref().and_then(|name| self.profile(name))
+    }
+
+    /// TLS settings of the active remote server.
+    pub fn active_tls(&self) -> &RemoteTls {
+        self.active_profile()
+            .and_then(|profile| profile.tls.as_ref())
+            .unwrap_or(&self.tls)
+    }
+
+    /// URL of the active remote server.
+    ///
+    /// Returns `None` if the remote server is disabled. Falls back to `url`
+    /// if the default profile is not set or not found.
+    pub fn active_url(&self) -> Option<&str> {
+        if !self.enabled.unwrap_or_default() {
+            return None;
+        }
+        if let Some(profile) = self.active_profile() {
+            return Some(profile.url.as_str());
+        }
+        self.url.as_deref()
+    }
+
+    /// Select a saved profile as default.
+    ///
+    /// Selecting [`LOCAL_PROFILE`](Self::LOCAL_PROFILE) disables the remote server.
+    pub fn select(&mut self, name: &str) -> Result<()> {
+        if name == Self::LOCAL_PROFILE {
+            self.enabled = Some(false);
+            self.default = Some(name.to_string());
+            return Ok(());
+        }
+        if self.profile(name).is_none() {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                format!("remote server profile `{}` not found", name),
+            ));
+        }
+        self.enabled = Some(true);
+        self.default = Some(name.to_string());
+        Ok(())
+    }
 }
 
-#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct Updater {
     pub enabled: Option<bool>,
 }
 
-#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct Window {
     /// Whether the window should be centered upon creation.
     pub center: Option<bool>,
@@ -47,18 +176,48 @@ pub struct Window {
     pub y: Option<i32>,
 }
 
-#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct Workspace {
+    /// Workspace name.
+    pub name: String,
+    /// Directory where Memos will store its database and assets.
+    pub data: Option<String>,
+    /// Server mode. Can be one of: prod, dev, demo.
+    pub mode: Option<String>,
+    /// Preferred Memos port. Set to 0 to pick a free port.
+    pub port: Option<u16>,
+    /// Directory where Memospot will store backups.
+    pub backups: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct Workspaces {
+    /// Name of the active workspace.
+    ///
+    /// The `memos` section and `memospot.backups.path` always hold the settings
+    /// of the active workspace. Other workspaces are stored in `list` and swapped
+    /// in when switching.
+    pub active: Option<String>,
+    /// Saved workspaces.
+    pub list: Option<Vec<Workspace>>,
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct Memospot {
     /// Backups settings.
     pub backups: Backups,
     /// Database migrations settings.
     pub migrations: Migrations,
-    // Log settings.
+    /// Log settings.
     pub log: Log,
+    /// Network settings.
+    pub network: Network,
     /// Remote server settings.
     pub remote: RemoteServer,
     /// Updater settings.
     pub updater: Updater,
     /// Window settings.
     pub window: Window,
+    /// Workspaces settings.
+    pub workspaces: Workspaces,
 }
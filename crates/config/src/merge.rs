@@ -0,0 +1,73 @@
+//! Comparing and merging configurations.
+//!
+//! Used to apply external edits to a running app, and to save in-memory changes
+//! without discarding edits made to the file in the meantime.
+
+use crate::yaml::{get, remove, segment, set};
+use crate::Config;
+use serde_yaml::Value;
+use std::io::{Error, ErrorKind, Result};
+
+/// List the dotted paths of leaf values that differ between two values.
+///
+/// Sequences are compared as a whole.
+fn changed_keys(
+    path: &mut Vec<String>,
+    old: Option<&Value>,
+    new: Option<&Value>,
+) -> Vec<String> {
+    match (old, new) {
+        (Some(Value::Mapping(old_map)), Some(Value::Mapping(new_map))) => {
+            let mut keys: Vec<String> = Vec::new();
+            for key in old_map
+                .keys()
+                .chain(new_map.keys().filter(|k| !old_map.contains_key(*k)))
+            {
+                path.push(segment(key));
+                keys.extend(changed_keys(path, old_map.get(key), new_map.get(key)));
+                path.pop();
+            }
+            keys
+        }
+        (old, new) if old == new => Vec::new(),
+        _ => Vec::from([path.join(".")]),
+    }
+}
+
+fn to_value(config: &Config) -> Result<Value> {
+    serde_yaml::to_value(config).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+}
+
+impl Config {
+    /// List the dotted keys whose values differ from another configuration.
+    pub fn diff(&self, other: &Config) -> Vec<String> {
+        let (Ok(old), Ok(new)) = (to_value(self), to_value(other)) else {
+            return Vec::new();
+        };
+        changed_keys(&mut Vec::new(), Some(&old), Some(&new))
+    }
+
+    /// Three-way merge of configurations sharing a common `base`.
+    ///
+    /// Starts from `theirs` and applies every key changed in `ours`.
+    /// Keys changed on both sides keep the value from `ours`.
+    pub fn merge(base: &Config, ours: &Config, theirs: &Config) -> Result<Config> {
+        let ours_value = to_value(ours)?;
+        let mut merged = to_value(theirs)?;
+        for key in base.diff(ours) {
+            let mut path: Vec<String> = key.split('.').map(str::to_string).collect();
+            // If `theirs` removed the parent, take the whole parent from `ours`.
+            while !path.is_empty() {
+                let applied = match get(&ours_value, &path) {
+                    Some(value) => set(&mut merged, &path, value.clone()),
+                    None => remove(&mut merged, &path) || get(&merged, &path).is_none(),
+                };
+                if applied {
+                    break;
+                }
+                path.pop();
+            }
+        }
+        serde_yaml::from_value(merged).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+    }
+}
@@ -0,0 +1,182 @@
+//! Origin of effective settings.
+//!
+//! Settings may come from defaults, the system, user or policy files, profile
+//! sections or environment variables. Figment tags each value with the provider
+//! it came from, which tells why a setting has a given value.
+
+use crate::env::env_name;
+use crate::yaml::{get, leaves};
+use crate::{Config, ConfigLayers};
+use figment::{Figment, Metadata, Profile, Source};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{Error, ErrorKind, Result};
+use std::path::PathBuf;
+
+/// Where an effective setting comes from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Origin {
+    /// Dotted setting key, such as `memos.mode`.
+    pub key: String,
+    /// Effective value, as JSON. Secrets are redacted.
+    pub value: String,
+    /// Default value, as JSON. `None` for keys without a default, such as `memos.env` entries.
+    pub default: Option<String>,
+    /// Source of the value, such as `default` or ``environment variable `MEMOSPOT_MEMOS__MODE` ``.
+    pub source: String,
+    /// Profile section the value comes from, if any.
+    pub profile: Option<String>,
+}
+
+impl Origin {
+    /// Whether the effective value is the default one.
+    pub fn is_default(&self) -> bool {
+        self.default.as_ref() == Some(&self.value)
+    }
+}
+
+/// Configuration layer a value comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Layer {
+    /// Built-in defaults.
+    Default,
+    /// System file.
+    System(PathBuf),
+    /// User file.
+    User(PathBuf),
+    /// Policy file.
+    Policy(PathBuf),
+    /// Environment variable, by name.
+    Env(String),
+}
+
+impl fmt::Display for Layer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Layer::Default => write!(f, "default"),
+            Layer::System(path) => write!(f, "system file `{}`", path.to_string_lossy()),
+            Layer::User(path) => write!(f, "user file `{}`", path.to_string_lossy()),
+            Layer::Policy(path) => write!(f, "policy file `{}`", path.to_string_lossy()),
+            Layer::Env(name) => write!(f, "environment variable `{}`", name),
+        }
+    }
+}
+
+/// Leaf settings of a configuration, with their values as JSON. Secrets are redacted.
+fn leaf_values(config: &Config) -> Vec<(String, String)> {
+    let Ok(value) = serde_yaml::to_value(config.redacted()) else {
+        return Vec::new();
+    };
+    if !value.is_mapping() {
+        return Vec::new();
+    }
+    leaves(&value)
+        .into_iter()
+        .filter_map(|path| {
+            let json = serde_json::to_string(get(&value, &path)?).ok()?;
+            Some((path.join("."), json))
+        })
+        .collect()
+}
+
+impl ConfigLayers {
+    /// Layer described by a provider's metadata, for the setting `key`.
+    pub(crate) fn layer(&self, metadata: &Metadata, key: &str) -> Layer {
+        match &metadata.source {
+            Some(Source::File(path)) if Some(path) == self.policy.as_ref() => {
+                Layer::Policy(path.clone())
+            }
+            Some(Source::File(path)) if Some(path) == self.system.as_ref() => {
+                Layer::System(path.clone())
+            }
+            Some(Source::File(path)) => Layer::User(path.clone()),
+            _ if metadata.name.contains("environment variable") => {
+                let path: Vec<String> = key.split('.').map(str::to_string).collect();
+                Layer::Env(env_name(&path))
+            }
+            _ => Layer::Default,
+        }
+    }
+
+    /// Layer the effective value of a setting comes from.
+    pub(crate) fn layer_of(&self, figment: &Figment, key: &str) -> Layer {
+        figment
+            .find_value(key)
+            .ok()
+            .and_then(|value| figment.get_metadata(value.tag()))
+            .map_or(Layer::Default, |metadata| self.layer(metadata, key))
+    }
+
+    /// Describe the provider a setting comes from, with its profile section.
+    fn source_of(&self, figment: &Figment, key: &str) -> (String, Option<String>) {
+        let Ok(value) = figment.find_value(key) else {
+            return (Layer::Default.to_string(), None);
+        };
+        let tag = value.tag();
+        let Some(metadata) = figment.get_metadata(tag) else {
+            return (Layer::Default.to_string(), None);
+        };
+
+        let layer = self.layer(metadata, key);
+        let section = matches!(layer, Layer::System(_) | Layer::User(_));
+        let source = layer.to_string();
+        // Tags only tell apart the default and global profiles. Other profiles
+        // are the selected one, as the others aren't merged.
+        let profile = match tag.profile() {
+            None => Some(figment.profile().as_str().to_string()),
+            Some(profile) if section && profile == Profile::Global => {
+                Some(profile.as_str().to_string())
+            }
+            Some(_) => None,
+        };
+        (source, profile)
+    }
+
+    /// List every effective setting, with where its value comes from.
+    pub fn origins(&self) -> Result<Vec<Origin>> {
+        let figment = self.figment();
+        let config = figment
+            .extract::<Config>()
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        let defaults: BTreeMap<String, String> =
+            leaf_values(&Config::default()).into_iter().collect();
+
+        Ok(leaf_values(&config)
+            .into_iter()
+            .map(|(key, value)| {
+                let (source, profile) = self.source_of(&figment, &key);
+                Origin {
+                    default: defaults.get(&key).cloned(),
+                    key,
+                    value,
+                    source,
+                    profile,
+                }
+            })
+            .collect())
+    }
+}
+
+impl Config {
+    /// Attribute the settings changed after loading, such as by the app itself, to `source`.
+    ///
+    /// `origins` describe the loaded configuration, see [`ConfigLayers::origins`].
+    pub fn override_origins(&self, origins: Vec<Origin>, source: &str) -> Vec<Origin> {
+        let defaults: BTreeMap<String, String> =
+            leaf_values(&Config::default()).into_iter().collect();
+        leaf_values(self)
+            .into_iter()
+            .map(|(key, value)| match origins.iter().find(|o| o.key == key) {
+                Some(origin) if origin.value == value => origin.clone(),
+                _ => Origin {
+                    default: defaults.get(&key).cloned(),
+                    key,
+                    value,
+                    source: source.to_string(),
+                    profile: None,
+                },
+            })
+            .collect()
+    }
+}
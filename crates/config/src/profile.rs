@@ -0,0 +1,244 @@
+//! Profile sections in configuration files.
+//!
+//! Besides top-level settings, configuration files may have sections named after
+//! profiles. When a profile is selected, its section overrides the top-level settings:
+//! ```yaml
+//! memos:
+//!   mode: prod
+//! debug:
+//!   memos:
+//!     mode: demo
+//! ```
+//! A `default` section is the same as top-level settings, and a `global` section
+//! overrides every profile.
+//!
+//! Besides `default`, `global`, `debug` and `release`, profiles must be declared
+//! in the `profiles` list, so that a misspelled setting such as `memsopot:` isn't
+//! taken for a profile section:
+//! ```yaml
+//! profiles: [work]
+//! work:
+//!   memos:
+//!     port: 5231
+//! ```
+
+use crate::yaml::{get, insert, leaves};
+use crate::Config;
+use figment::value::{Dict, Map, Value as FigmentValue};
+use figment::{Error, Metadata, Profile, Provider};
+use serde_yaml::Value;
+
+/// Profiles that may have a section without being declared.
+const BUILTIN_PROFILES: &[&str] = &["default", "global", "debug", "release"];
+
+/// Top-level keys of the configuration.
+fn config_keys() -> Vec<String> {
+    match serde_yaml::to_value(Config::default()) {
+        Ok(Value::Mapping(map)) => map
+            .keys()
+            .filter_map(Value::as_str)
+            .map(str::to_string)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Whether a profile may have a section, given the declared profiles.
+fn is_profile(key: &str, declared: &[String]) -> bool {
+    BUILTIN_PROFILES
+        .iter()
+        .copied()
+        .chain(declared.iter().map(String::as_str))
+        .any(|name| Profile::new(name) == Profile::new(key))
+        && !config_keys().iter().any(|k| k == key)
+}
+
+/// Profiles declared in the `profiles` list of a configuration document.
+fn declared_profiles(doc: &Value) -> Vec<String> {
+    doc.get("profiles")
+        .and_then(Value::as_sequence)
+        .map(|names| {
+            names
+                .iter()
+                .filter_map(Value::as_str)
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Whether a top-level key of a configuration document is a profile section.
+///
+/// Other top-level keys, including undeclared mappings, are settings.
+pub(crate) fn is_section(doc: &Value, key: &str) -> bool {
+    doc.get(key).is_some_and(Value::is_mapping) && is_profile(key, &declared_profiles(doc))
+}
+
+/// Profile sections of a configuration document, as spelled in the document.
+pub(crate) fn sections(doc: &Value) -> Vec<String> {
+    let Value::Mapping(map) = doc else {
+        return Vec::new();
+    };
+    map.keys()
+        .filter_map(Value::as_str)
+        .filter(|key| is_section(doc, key))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Merge a dictionary into another, key by key.
+pub(crate) fn merge_dict(into: &mut Dict, from: Dict) {
+    for (key, value) in from {
+        match (into.get_mut(&key), value) {
+            (Some(FigmentValue::Dict(_, into)), FigmentValue::Dict(_, from)) => {
+                merge_dict(into, from)
+            }
+            (_, value) => {
+                into.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Provider splitting a configuration file into its profile sections.
+///
+/// Top-level settings go to `base`: the default profile, unless changed.
+pub(crate) struct Sections<P: Provider> {
+    provider: P,
+    base: Profile,
+}
+
+impl<P: Provider> Sections<P> {
+    pub(crate) fn new(provider: P) -> Self {
+        Self {
+            provider,
+            base: Profile::Default,
+        }
+    }
+
+    /// Put top-level settings in another profile, such as [`Profile::Global`].
+    pub(crate) fn base(mut self, profile: Profile) -> Self {
+        self.base = profile;
+        self
+    }
+}
+
+impl<P: Provider> Provider for Sections<P> {
+    fn metadata(&self) -> Metadata {
+        self.provider.metadata()
+    }
+
+    fn data(&self) -> Result<Map<Profile, Dict>, Error> {
+        let keys = config_keys();
+        let mut data: Map<Profile, Dict> = Map::new();
+        for dict in self.provider.data()?.into_values() {
+            let declared: Vec<String> = match dict.get("profiles") {
+                Some(FigmentValue::Array(_, names)) => names
+                    .iter()
+                    .filter_map(FigmentValue::as_str)
+                    .map(str::to_string)
+                    .collect(),
+                _ => Vec::new(),
+            };
+            let mut base = Dict::new();
+            for (key, value) in dict {
+                match value {
+                    FigmentValue::Dict(_, section)
+                        if !keys.contains(&key) && is_profile(&key, &declared) =>
+                    {
+                        merge_dict(data.entry(Profile::new(&key)).or_default(), section);
+                    }
+                    value => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+            // Top-level settings and the `default` section are the same.
+            merge_dict(data.entry(self.base.clone()).or_default(), base);
+        }
+        Ok(data)
+    }
+}
+
+/// Name of a document section matching a profile, as spelled in the document.
+fn section_key(doc: &Value, profile: &Profile) -> Option<String> {
+    sections(doc)
+        .into_iter()
+        .find(|key| Profile::new(key) == *profile)
+}
+
+/// Where to write a setting for the selected profile.
+///
+/// Settings defined in the `global`, selected or `default` sections are written
+/// there, so they aren't shadowed. Other settings go to the top level.
+pub(crate) fn write_path(doc: &Value, profile: &Profile, path: &[String]) -> Vec<String> {
+    for section in [&Profile::Global, profile, &Profile::Default] {
+        let Some(key) = section_key(doc, section) else {
+            continue;
+        };
+        let section_path: Vec<String> = [Vec::from([key]), path.to_vec()].concat();
+        if get(doc, &section_path).is_some() {
+            return section_path;
+        }
+    }
+    path.to_vec()
+}
+
+/// Configuration path of a document leaf, for the selected profile.
+///
+/// Returns `None` for leaves in sections of other profiles.
+pub(crate) fn config_path(
+    doc: &Value,
+    profile: &Profile,
+    leaf: &[String],
+) -> Option<Vec<String>> {
+    let Some(first) = leaf.first() else {
+        return Some(Vec::new());
+    };
+    if !is_section(doc, first) {
+        return Some(leaf.to_vec());
+    }
+    let section = Profile::new(first);
+    [&Profile::Global, profile, &Profile::Default]
+        .contains(&&section)
+        .then(|| leaf[1..].to_vec())
+}
+
+/// Merge the sections of the selected profile into the top-level settings.
+///
+/// Sections of other profiles are dropped, and returned as well.
+pub(crate) fn flatten(doc: &Value, profile: &Profile) -> (Value, Vec<String>) {
+    let Value::Mapping(map) = doc else {
+        return (doc.clone(), Vec::new());
+    };
+    let sections = sections(doc);
+    let mut flat = Value::Mapping(
+        map.iter()
+            .filter(|(key, _)| {
+                !key.as_str()
+                    .is_some_and(|k| sections.iter().any(|s| s == k))
+            })
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect(),
+    );
+    let merged = [&Profile::Default, profile, &Profile::Global];
+    let dropped: Vec<String> = sections
+        .iter()
+        .filter(|key| !merged.contains(&&Profile::new(key)))
+        .cloned()
+        .collect();
+    for section in merged {
+        let Some(key) = section_key(doc, section) else {
+            continue;
+        };
+        let Some(values) = get(doc, &[key]) else {
+            continue;
+        };
+        for leaf in leaves(values) {
+            if let Some(value) = get(values, &leaf) {
+                insert(&mut flat, &leaf, value.clone());
+            }
+        }
+    }
+    (flat, dropped)
+}
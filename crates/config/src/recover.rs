@@ -0,0 +1,238 @@
+//! Lenient recovery of malformed configuration files.
+//!
+//! Instead of resetting everything, the file is parsed as a generic YAML value
+//! and merged over the defaults key by key. Keys that don't fit the configuration
+//! structure or fail validation are dropped and reported.
+
+use crate::profile;
+use crate::validate::ValidationError;
+use crate::yaml::{get, remove, segment, set};
+use crate::{Config, ConfigLayers};
+use serde_yaml::Value;
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+
+/// Outcome of a configuration recovery.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recovery {
+    /// Recovered configuration, already saved to the configuration file.
+    pub config: Config,
+    /// Dropped keys, and why. Positions refer to the broken file.
+    pub dropped: Vec<ValidationError>,
+    /// Copy of the broken file.
+    pub backup: PathBuf,
+}
+
+/// Try to deserialize a candidate configuration.
+fn deserialize(candidate: &Value) -> std::result::Result<Config, serde_yaml::Error> {
+    serde_yaml::from_value::<Config>(candidate.clone())
+}
+
+/// Recovery state: a candidate configuration and what was dropped so far.
+struct Salvage {
+    candidate: Value,
+    dropped: Vec<ValidationError>,
+}
+
+impl Salvage {
+    /// Try to set a value, restoring the previous one if it doesn't fit.
+    fn try_set(&mut self, path: &[String], value: Value) -> bool {
+        let previous = get(&self.candidate, path).cloned();
+        if !set(&mut self.candidate, path, value) {
+            self.dropped.push(ValidationError::new(
+                &path.join("."),
+                "unexpected value at this position",
+            ));
+            return false;
+        }
+        let Err(e) = deserialize(&self.candidate) else {
+            return true;
+        };
+
+        match previous {
+            Some(previous) => set(&mut self.candidate, path, previous),
+            None => remove(&mut self.candidate, path),
+        };
+        self.dropped
+            .push(ValidationError::new(&path.join("."), e.to_string()));
+        false
+    }
+
+    /// Merge a user value over the candidate, one key at a time.
+    fn merge(&mut self, path: &mut Vec<String>, value: &Value) {
+        match value {
+            Value::Mapping(map) => {
+                let is_mapping = matches!(get(&self.candidate, path), Some(Value::Mapping(_)));
+                if !is_mapping && !self.try_set(path, Value::Mapping(Default::default())) {
+                    self.dropped.pop();
+                    self.try_set(path, value.clone());
+                    return;
+                }
+                for (key, value) in map {
+                    path.push(segment(key));
+                    self.merge(path, value);
+                    path.pop();
+                }
+            }
+            Value::Sequence(seq) => {
+                if !self.try_set(path, Value::Sequence(Vec::new())) {
+                    self.dropped.pop();
+                    self.try_set(path, value.clone());
+                    return;
+                }
+                let mut kept = 0;
+                for (index, item) in seq.iter().enumerate() {
+                    path.push(kept.to_string());
+                    if self.try_set(path, item.clone()) {
+                        kept += 1;
+                    } else if let Some(dropped) = self.dropped.last_mut() {
+                        // Report the index in the original file.
+                        let parent = path[..path.len() - 1].join(".");
+                        dropped.key = format!("{}.{}", parent, index);
+                    }
+                    path.pop();
+                }
+            }
+            _ => {
+                self.try_set(path, value.clone());
+            }
+        }
+    }
+
+    /// Reset or remove a key that failed validation, walking up until the result fits.
+    fn drop_invalid(&mut self, error: &ValidationError, defaults: &Value) {
+        let mut path: Vec<String> = error.key.split('.').map(str::to_string).collect();
+        while !path.is_empty() {
+            let mut candidate = self.candidate.clone();
+            let changed = match get(defaults, &path) {
+                Some(default) => set(&mut candidate, &path, default.clone()),
+                None => remove(&mut candidate, &path),
+            };
+            if changed && deserialize(&candidate).is_ok() {
+                self.candidate = candidate;
+                self.dropped
+                    .push(ValidationError::new(&path.join("."), error.message.clone()));
+                return;
+            }
+            path.pop();
+        }
+    }
+}
+
+/// List mapping keys from the user value that are missing from the recovered one.
+///
+/// Sequences are not descended into, as dropped items shift indices.
+fn unknown_keys(value: &Value, recovered: &Value, path: &mut Vec<String>) -> Vec<String> {
+    let mut unknown: Vec<String> = Vec::new();
+    let Value::Mapping(map) = value else {
+        return unknown;
+    };
+    for (key, value) in map {
+        path.push(segment(key));
+        match get(recovered, path) {
+            Some(_) => unknown.extend(unknown_keys(value, recovered, path)),
+            None => unknown.push(path.join(".")),
+        }
+        path.pop();
+    }
+    unknown
+}
+
+impl Config {
+    /// Recover configuration from a generic YAML value.
+    ///
+    /// Returns the recovered configuration and the dropped keys.
+    fn recover(value: &Value) -> Result<(Config, Vec<ValidationError>)> {
+        if !matches!(value, Value::Mapping(_) | Value::Null) {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "configuration file is not a YAML mapping",
+            ));
+        }
+
+        let defaults = serde_yaml::to_value(Config::default())
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        let mut salvage = Salvage {
+            candidate: defaults.clone(),
+            dropped: Vec::new(),
+        };
+        if !value.is_null() {
+            salvage.merge(&mut Vec::new(), value);
+        }
+
+        // Drop invalid values one at a time, as a single key may cause several errors.
+        loop {
+            let config = deserialize(&salvage.candidate)
+                .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+            let Some(error) = config.validate().into_iter().next() else {
+                break;
+            };
+            let dropped = salvage.dropped.len();
+            salvage.drop_invalid(&error, &defaults);
+            if salvage.dropped.len() == dropped {
+                return Err(Error::new(ErrorKind::InvalidData, error.to_string()));
+            }
+        }
+
+        let config = deserialize(&salvage.candidate)
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        let recovered =
+            serde_yaml::to_value(&config).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        for key in unknown_keys(value, &recovered, &mut Vec::new()) {
+            let covered = salvage
+                .dropped
+                .iter()
+                .any(|d| key == d.key || key.starts_with(&format!("{}.", d.key)));
+            if !covered {
+                salvage
+                    .dropped
+                    .push(ValidationError::new(&key, "unknown key"));
+            }
+        }
+
+        Ok((config, salvage.dropped))
+    }
+
+    /// Repair a malformed configuration file, keeping every valid setting.
+    ///
+    /// See [`ConfigLayers::recover`].
+    pub fn recover_file(cfg_path: &Path) -> Result<Recovery> {
+        ConfigLayers::from(cfg_path).recover()
+    }
+}
+
+impl ConfigLayers {
+    /// Repair a malformed user file, keeping every valid setting.
+    ///
+    /// The broken file is copied next to the original, with a `.broken` suffix,
+    /// and the recovered configuration is saved in its place. Profile sections
+    /// are merged into the top-level settings for the selected profile, and
+    /// sections of other profiles are dropped.
+    /// Fails if the file isn't valid YAML at all.
+    pub fn recover(&self) -> Result<Recovery> {
+        let cfg_path = self.user.as_path();
+        let contents = fs::read_to_string(cfg_path)?;
+        let mut value: Value = serde_yaml::from_str(&contents)
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        Config::upgrade(&mut value)?;
+        let profile = self.selected_profile();
+        let (value, sections) = profile::flatten(&value, &profile);
+        let (config, mut dropped) = Config::recover(&value)?;
+        dropped.extend(
+            sections.iter().map(|key| {
+                ValidationError::new(key, "section of a profile that isn't selected")
+            }),
+        );
+
+        let backup = Config::sibling_path(cfg_path, ".broken");
+        fs::copy(cfg_path, &backup)?;
+        Config::save_profile(cfg_path, &config, &profile)?;
+
+        Ok(Recovery {
+            config,
+            dropped: dropped.into_iter().map(|d| d.locate(&contents)).collect(),
+            backup,
+        })
+    }
+}
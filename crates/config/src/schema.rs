@@ -0,0 +1,72 @@
+//! JSON Schema of the configuration file.
+//!
+//! Lets editors validate and autocomplete `memospot.yaml`. Descriptions come from
+//! the doc comments of the configuration types, and defaults from [`Config::default`].
+
+use crate::Config;
+use schemars::gen::SchemaSettings;
+use serde_json::Value;
+use std::io::{Error, ErrorKind, Result};
+
+/// Add default values to the properties of a schema, recursively.
+///
+/// Properties with a default are no longer required, as missing keys are filled in.
+fn set_defaults(schema: &mut Value, defaults: &Value) {
+    let Some(defaults) = defaults.as_object() else {
+        return;
+    };
+    let Some(schema) = schema.as_object_mut() else {
+        return;
+    };
+    if let Some(Value::Array(required)) = schema.get_mut("required") {
+        required.retain(|key| {
+            key.as_str()
+                .and_then(|key| defaults.get(key))
+                .is_none_or(Value::is_null)
+        });
+        if required.is_empty() {
+            schema.remove("required");
+        }
+    }
+    let Some(Value::Object(properties)) = schema.get_mut("properties") else {
+        return;
+    };
+    for (key, property) in properties {
+        let Some(default) = defaults.get(key) else {
+            continue;
+        };
+        if let (Some(property), false) = (property.as_object_mut(), default.is_null()) {
+            property.insert("default".to_string(), default.clone());
+        }
+        set_defaults(property, default);
+    }
+}
+
+impl Config {
+    /// Where the published schema can be found. Referenced by the configuration file header.
+    pub const SCHEMA_URL: &'static str =
+        "https://raw.githubusercontent.com/memospot/memospot/main/crates/config/memospot.schema.json";
+
+    /// JSON Schema of the configuration file, with defaults.
+    pub(crate) fn schema_value() -> Result<Value> {
+        let generator = SchemaSettings::draft07()
+            .with(|s| s.inline_subschemas = true)
+            .into_generator();
+        let root = generator.into_root_schema_for::<Config>();
+
+        let mut schema =
+            serde_json::to_value(root).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        let defaults = serde_json::to_value(Config::default())
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        set_defaults(&mut schema, &defaults);
+        Ok(schema)
+    }
+
+    /// Generate the JSON Schema of the configuration file.
+    pub fn json_schema() -> Result<String> {
+        let mut json = serde_json::to_string_pretty(&Self::schema_value()?)
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        json.push('\n');
+        Ok(json)
+    }
+}
@@ -0,0 +1,149 @@
+//! Configuration upgrades between Memospot releases.
+//!
+//! Works like the database migrator: each upgrade transforms a YAML document
+//! from one version to the next, before it's extracted into [`Config`].
+//! Documents without a `version` key are version 0.
+//!
+//! To rename or restructure a key, add an upgrade to the end of [`upgrades`].
+//! Never change or reorder existing upgrades.
+//!
+//! Files are upgraded in memory whenever they're read, see [`UpgradedFile`].
+//! They're only rewritten on disk by [`ConfigLayers::upgrade`], once they're
+//! known to be valid.
+
+use crate::{Config, ConfigLayers};
+use figment::providers::{Format, Yaml};
+use figment::value::{Dict, Map};
+use figment::{Metadata, Profile, Provider};
+use serde_yaml::{Mapping, Value};
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::PathBuf;
+
+/// A single configuration upgrade step.
+pub(crate) trait ConfigUpgrade {
+    /// Short description, used in logs and errors.
+    fn name(&self) -> &'static str;
+    /// Transform a document from the previous version to this one.
+    fn up(&self, doc: &mut Mapping) -> Result<()>;
+}
+
+/// Version 1: introduce the `version` key. The layout is unchanged.
+struct V001AddVersion;
+
+impl ConfigUpgrade for V001AddVersion {
+    fn name(&self) -> &'static str {
+        "add version key"
+    }
+
+    fn up(&self, _doc: &mut Mapping) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Upgrade list. The document version after running the n-th upgrade is n.
+pub(crate) fn upgrades() -> Vec<Box<dyn ConfigUpgrade>> {
+    vec![Box::new(V001AddVersion)]
+}
+
+impl Config {
+    /// Current configuration version.
+    pub const VERSION: u32 = 1;
+
+    /// Upgrade a configuration document to the current version.
+    ///
+    /// Returns true if the document changed. Fails if the document
+    /// was written by a newer Memospot release.
+    pub(crate) fn upgrade(doc: &mut Value) -> Result<bool> {
+        let Value::Mapping(map) = doc else {
+            return Ok(false); // Empty or malformed: nothing to upgrade.
+        };
+
+        let version = match map.get("version") {
+            None | Some(Value::Null) => 0,
+            Some(value) => value.as_u64().ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    "configuration `version` must be a positive integer",
+                )
+            })?,
+        };
+        if version > Config::VERSION as u64 {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "configuration version {} is newer than the supported version {}; \
+                    it was probably written by a newer Memospot release",
+                    version,
+                    Config::VERSION
+                ),
+            ));
+        }
+        if version == Config::VERSION as u64 {
+            return Ok(false);
+        }
+
+        for (index, upgrade) in upgrades().iter().enumerate().skip(version as usize) {
+            upgrade.up(map).map_err(|e| {
+                Error::new(
+                    e.kind(),
+                    format!(
+                        "configuration upgrade to version {} ({}) failed: {}",
+                        index + 1,
+                        upgrade.name(),
+                        e
+                    ),
+                )
+            })?;
+        }
+        map.insert(
+            Value::String("version".to_string()),
+            Value::Number(Config::VERSION.into()),
+        );
+        Ok(true)
+    }
+}
+
+/// A YAML configuration file, upgraded in memory if it was written by an older release.
+///
+/// Files from newer releases fail to load.
+pub(crate) struct UpgradedFile(pub(crate) PathBuf);
+
+impl Provider for UpgradedFile {
+    fn metadata(&self) -> Metadata {
+        Yaml::file(&self.0).metadata()
+    }
+
+    fn data(&self) -> std::result::Result<Map<Profile, Dict>, figment::Error> {
+        let file = Yaml::file(&self.0);
+        // Missing and malformed files are reported by the YAML provider.
+        let Ok(contents) = fs::read_to_string(&self.0) else {
+            return file.data();
+        };
+        let Ok(mut doc) = serde_yaml::from_str::<Value>(&contents) else {
+            return file.data();
+        };
+        match Config::upgrade(&mut doc) {
+            Ok(true) => {
+                let upgraded = serde_yaml::to_string(&doc)
+                    .map_err(|e| figment::Error::from(e.to_string()))?;
+                Yaml::string(&upgraded).data()
+            }
+            Ok(false) => file.data(),
+            Err(e) => Err(figment::Error::from(e.to_string())),
+        }
+    }
+}
+
+impl ConfigLayers {
+    /// Upgrade the user file on disk, if it was written by an older release.
+    ///
+    /// Only valid files are upgraded, so that a broken file is never rewritten
+    /// before it can be repaired. Returns true if the file was upgraded.
+    pub fn upgrade(&self) -> Result<bool> {
+        if !self.validate().is_empty() {
+            return Ok(false);
+        }
+        Config::write_upgrade(&self.user)
+    }
+}
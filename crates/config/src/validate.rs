@@ -0,0 +1,341 @@
+//! Configuration validation.
+//!
+//! Besides the type checks done while parsing, validates values that would
+//! otherwise only fail at runtime, such as unknown server modes or bad URLs.
+
+use crate::log::Log;
+use crate::memospot::RemoteServer;
+use crate::origin::Layer;
+use crate::{profile, yaml, Config, ConfigLayers};
+use figment::Profile;
+use std::fmt;
+use std::fs;
+use std::net::IpAddr;
+use std::path::Path;
+
+/// A configuration error, pointing to the offending key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationError {
+    /// Dotted path to the offending key, such as `memos.mode`.
+    ///
+    /// Empty for errors affecting the whole file.
+    pub key: String,
+    /// What's wrong with the value.
+    pub message: String,
+    /// 1-based line in the configuration file, if known.
+    pub line: Option<usize>,
+    /// 1-based column in the configuration file, if known.
+    pub column: Option<usize>,
+    /// Layer the offending value comes from, if known.
+    ///
+    /// Only set by [`ConfigLayers::validate`]. Line and column are only
+    /// filled in for the user file.
+    pub layer: Option<Layer>,
+}
+
+impl ValidationError {
+    pub(crate) fn new(key: &str, message: impl Into<String>) -> Self {
+        Self {
+            key: key.to_string(),
+            message: message.into(),
+            line: None,
+            column: None,
+            layer: None,
+        }
+    }
+
+    /// Whether the error can be fixed in the user file.
+    ///
+    /// Errors in environment variables, the system file or the policy file can't.
+    pub fn in_user_file(&self) -> bool {
+        !matches!(
+            self.layer,
+            Some(Layer::Env(_) | Layer::System(_) | Layer::Policy(_))
+        )
+    }
+
+    /// Fill in the position of the key in the configuration file.
+    pub(crate) fn locate(mut self, contents: &str) -> Self {
+        if let Some((line, column)) = yaml::locate(contents, &self.key) {
+            self.line = Some(line);
+            self.column = Some(column);
+        }
+        self
+    }
+
+    /// Fill in the position of the key, looking in the section of `profile` first.
+    fn locate_in_profile(mut self, contents: &str, profile: &Profile) -> Self {
+        let Ok(doc) = serde_yaml::from_str::<serde_yaml::Value>(contents) else {
+            return self.locate(contents);
+        };
+        let path: Vec<String> = self.key.split('.').map(str::to_string).collect();
+        let key = std::mem::replace(
+            &mut self.key,
+            profile::write_path(&doc, profile, &path).join("."),
+        );
+        let mut located = self.locate(contents);
+        located.key = key;
+        located
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.in_user_file() {
+            if let Some(layer) = &self.layer {
+                write!(f, "{}: ", layer)?;
+            }
+        }
+        if let Some(line) = self.line {
+            write!(f, "line {}", line)?;
+            if let Some(column) = self.column {
+                write!(f, ", column {}", column)?;
+            }
+            write!(f, ": ")?;
+        }
+        if !self.key.is_empty() {
+            write!(f, "`{}`: ", self.key)?;
+        }
+        write!(f, "{}", self.message)
+    }
+}
+
+/// Check that a URL uses one of the allowed schemes.
+fn check_url(
+    errors: &mut Vec<ValidationError>,
+    key: &str,
+    url: &Option<String>,
+    schemes: &[&str],
+) {
+    let Some(url) = url.as_deref().map(str::trim).filter(|u| !u.is_empty()) else {
+        return;
+    };
+    let valid = url
+        .split_once("://")
+        .is_some_and(|(scheme, rest)| schemes.contains(&scheme) && !rest.is_empty());
+    if !valid {
+        errors.push(ValidationError::new(
+            key,
+            format!(
+                "invalid URL `{}`; it must start with {}",
+                url,
+                schemes
+                    .iter()
+                    .map(|s| format!("{}://", s))
+                    .collect::<Vec<_>>()
+                    .join(" or ")
+            ),
+        ));
+    }
+}
+
+/// Check that a value is one of the allowed choices.
+fn check_choice(
+    errors: &mut Vec<ValidationError>,
+    key: &str,
+    value: &Option<String>,
+    choices: &[&str],
+) {
+    let Some(value) = value else {
+        return;
+    };
+    if !choices.contains(&value.as_str()) {
+        errors.push(ValidationError::new(
+            key,
+            format!(
+                "invalid value `{}`; it must be one of: {}",
+                value,
+                choices.join(", ")
+            ),
+        ));
+    }
+}
+
+impl Config {
+    /// Check configuration values that are valid YAML but can't work at runtime.
+    pub fn validate(&self) -> Vec<ValidationError> {
+        const HTTP: &[&str] = &["http", "https"];
+        let mut errors: Vec<ValidationError> = Vec::new();
+
+        check_choice(
+            &mut errors,
+            "memos.mode",
+            &self.memos.mode,
+            &["prod", "dev", "demo"],
+        );
+        if let Some(addr) = &self.memos.addr {
+            if addr.parse::<IpAddr>().is_err() {
+                errors.push(ValidationError::new(
+                    "memos.addr",
+                    format!("invalid IP address `{}`", addr),
+                ));
+            }
+        }
+
+        let remote = &self.memospot.remote;
+        check_url(&mut errors, "memospot.remote.url", &remote.url, HTTP);
+        check_choice(
+            &mut errors,
+            "memospot.remote.fallback",
+            &remote.fallback,
+            &["error", "retry", "local"],
+        );
+        for (index, profile) in remote.profiles.iter().flatten().enumerate() {
+            let key = format!("memospot.remote.profiles.{}", index);
+            if profile.name.trim().is_empty() {
+                errors.push(ValidationError::new(
+                    &format!("{}.name", key),
+                    "profile name must not be empty",
+                ));
+            } else if profile.name == RemoteServer::LOCAL_PROFILE {
+                errors.push(ValidationError::new(
+                    &format!("{}.name", key),
+                    format!("`{}` is a reserved profile name", profile.name),
+                ));
+            }
+            check_url(
+                &mut errors,
+                &format!("{}.url", key),
+                &Some(profile.url.clone()),
+                HTTP,
+            );
+        }
+        if let Some(default) = &remote.default {
+            if default != RemoteServer::LOCAL_PROFILE && remote.profile(default).is_none() {
+                errors.push(ValidationError::new(
+                    "memospot.remote.default",
+                    format!("remote profile `{}` not found", default),
+                ));
+            }
+        }
+
+        check_url(
+            &mut errors,
+            "memospot.network.proxy.url",
+            &self.memospot.network.proxy.url,
+            &["http", "https", "socks5", "socks5h"],
+        );
+
+        for (key, secret) in self.secret_refs() {
+            if secret.is_empty() {
+                errors.push(ValidationError::new(
+                    &key,
+                    "secret reference must name a secret or a file",
+                ));
+            }
+        }
+
+        let log = &self.memospot.log;
+        check_choice(&mut errors, "memospot.log.level", &log.level, Log::LEVELS);
+        for (target, level) in log.targets.iter().flatten() {
+            check_choice(
+                &mut errors,
+                &format!("memospot.log.targets.{}", target),
+                &Some(level.clone()),
+                Log::LEVELS,
+            );
+        }
+        check_choice(
+            &mut errors,
+            "memospot.log.format",
+            &log.format,
+            Log::FORMATS,
+        );
+        for (key, value) in [
+            ("memospot.log.rotate_size", log.rotate_size),
+            ("memospot.log.rotate_count", log.rotate_count.map(u64::from)),
+        ] {
+            if value == Some(0) {
+                errors.push(ValidationError::new(key, "must be greater than 0"));
+            }
+        }
+
+        let window = &self.memospot.window;
+        for (key, size) in [
+            ("memospot.window.width", window.width),
+            ("memospot.window.height", window.height),
+        ] {
+            if size == Some(0) {
+                errors.push(ValidationError::new(key, "must be greater than 0"));
+            }
+        }
+
+        let workspaces = &self.memospot.workspaces;
+        for (index, workspace) in workspaces.list.iter().flatten().enumerate() {
+            check_choice(
+                &mut errors,
+                &format!("memospot.workspaces.list.{}.mode", index),
+                &workspace.mode,
+                &["prod", "dev", "demo"],
+            );
+        }
+
+        errors
+    }
+
+    /// Validate a configuration file, including environment overrides.
+    ///
+    /// See [`ConfigLayers::validate`].
+    pub fn validate_file(cfg_path: &Path) -> Vec<ValidationError> {
+        ConfigLayers::from(cfg_path).validate()
+    }
+}
+
+impl ConfigLayers {
+    /// Validate the user file, including environment overrides and other layers.
+    ///
+    /// Reports syntax, type and semantic errors, with the layer the offending
+    /// value comes from. Errors in the user file have their position whenever
+    /// the offending key can be found. A missing file is valid.
+    pub fn validate(&self) -> Vec<ValidationError> {
+        let contents = match fs::read_to_string(&self.user) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Vec::from([ValidationError::new("", e.to_string())]),
+        };
+
+        if let Err(e) = serde_yaml::from_str::<serde_yaml::Value>(&contents) {
+            let mut error = ValidationError::new("", e.to_string());
+            if let Some(location) = e.location() {
+                error.line = Some(location.line());
+                error.column = Some(location.column());
+            }
+            return Vec::from([error]);
+        }
+
+        let profile = self.selected_profile();
+        let figment = self.figment();
+        let errors: Vec<ValidationError> = match figment.extract::<Config>() {
+            Ok(config) => config
+                .validate()
+                .into_iter()
+                .map(|mut error| {
+                    error.layer = Some(self.layer_of(&figment, &error.key));
+                    error
+                })
+                .collect(),
+            Err(errors) => errors
+                .into_iter()
+                .map(|e| {
+                    let key = e.path.join(".");
+                    let mut error = ValidationError::new(&key, e.kind.to_string());
+                    error.layer = e
+                        .metadata
+                        .as_ref()
+                        .map(|metadata| self.layer(metadata, &key));
+                    error
+                })
+                .collect(),
+        };
+        errors
+            .into_iter()
+            .map(|error| {
+                if error.in_user_file() {
+                    error.locate_in_profile(&contents, &profile)
+                } else {
+                    error
+                }
+            })
+            .collect()
+    }
+}
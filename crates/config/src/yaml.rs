@@ -0,0 +1,477 @@
+//! Line-based helpers for YAML configuration files.
+//!
+//! `serde_yaml` doesn't keep track of where each value came from,
+//! so keys are located by walking the document and tracking indentation.
+//! Only block-style mappings and sequences are supported, which is what
+//! Memospot writes and what users usually edit.
+
+use serde_yaml::{Mapping, Value};
+
+/// A mapping key or sequence item found in a YAML document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Node {
+    /// Dotted path to the node. Sequence items use their index, as in `list.0.name`.
+    pub path: String,
+    /// 0-based line index.
+    pub line: usize,
+    /// 0-based column where the key starts.
+    pub column: usize,
+}
+
+/// Parse a block-style key, returning it unquoted along with the rest of the line.
+fn split_key(text: &str) -> Option<(String, &str)> {
+    let (key, rest) =
+        if let Some(quote) = text.chars().next().filter(|c| *c == '"' || *c == '\'') {
+            let end = text[1..].find(quote)? + 1;
+            (text[1..end].to_string(), &text[end + 1..])
+        } else {
+            let end = text
+                .find(": ")
+                .or_else(|| text.strip_suffix(':').map(|k| k.len()))?;
+            (text[..end].trim_end().to_string(), &text[end..])
+        };
+
+    let rest = rest.strip_prefix(':')?;
+    if !rest.is_empty() && !rest.starts_with(' ') {
+        return None;
+    }
+    if key.is_empty() || key.starts_with('#') {
+        return None;
+    }
+    Some((key, rest.trim()))
+}
+
+/// Whether a value starts a block scalar, whose lines must not be parsed as keys.
+fn is_block_scalar(value: &str) -> bool {
+    let value = value.split(" #").next().unwrap_or_default().trim();
+    value.starts_with('|') || value.starts_with('>')
+}
+
+/// List every mapping key and sequence item in a YAML document.
+pub(crate) fn nodes(contents: &str) -> Vec<Node> {
+    // (indentation, path segment, whether it's a sequence item)
+    let mut stack: Vec<(usize, String, bool)> = Vec::new();
+    let mut counters: Vec<(String, usize)> = Vec::new();
+    let mut nodes: Vec<Node> = Vec::new();
+    let mut block_indent: Option<usize> = None;
+
+    let path_of = |stack: &Vec<(usize, String, bool)>| -> String {
+        stack
+            .iter()
+            .map(|(_, segment, _)| segment.as_str())
+            .collect::<Vec<_>>()
+            .join(".")
+    };
+
+    for (line, text) in contents.lines().enumerate() {
+        let trimmed = text.trim_start();
+        let mut indent = text.len() - trimmed.len();
+
+        if let Some(parent_indent) = block_indent {
+            if trimmed.is_empty() || indent > parent_indent {
+                continue;
+            }
+            block_indent = None;
+        }
+        if trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with("---") {
+            continue;
+        }
+
+        let mut rest = trimmed;
+        while rest == "-" || rest.starts_with("- ") {
+            while stack
+                .last()
+                .is_some_and(|(i, _, item)| *i > indent || (*i == indent && *item))
+            {
+                stack.pop();
+            }
+
+            let parent = path_of(&stack);
+            let index = match counters.iter_mut().find(|(p, _)| *p == parent) {
+                Some((_, count)) => {
+                    *count += 1;
+                    *count - 1
+                }
+                None => {
+                    counters.push((parent.clone(), 1));
+                    0
+                }
+            };
+            stack.push((indent, index.to_string(), true));
+            nodes.push(Node {
+                path: path_of(&stack),
+                line,
+                column: indent,
+            });
+
+            let after = rest[1..].trim_start();
+            indent += rest.len() - after.len();
+            rest = after;
+        }
+
+        let Some((key, value)) = split_key(rest) else {
+            continue;
+        };
+        while stack.last().is_some_and(|(i, _, _)| *i >= indent) {
+            stack.pop();
+        }
+
+        // Restart the item counter of a key seen again.
+        stack.push((indent, key, false));
+        let path = path_of(&stack);
+        counters.retain(|(p, _)| *p != path);
+        nodes.push(Node {
+            path,
+            line,
+            column: indent,
+        });
+
+        if is_block_scalar(value) {
+            block_indent = Some(indent);
+        }
+    }
+    nodes
+}
+
+/// Find the 1-based line and column of a dotted key path.
+pub(crate) fn locate(contents: &str, path: &str) -> Option<(usize, usize)> {
+    nodes(contents)
+        .into_iter()
+        .find(|node| node.path == path)
+        .map(|node| (node.line + 1, node.column + 1))
+}
+
+/// Byte offsets of a key's colon, and of its inline value, excluding trailing comments.
+fn value_span(text: &str, column: usize) -> Option<(usize, usize, usize)> {
+    let rest = text.get(column..)?;
+    let key_end = match rest.chars().next() {
+        Some(quote) if quote == '"' || quote == '\'' => rest[1..].find(quote)? + 2,
+        _ => rest
+            .find(": ")
+            .or_else(|| rest.find(":\t"))
+            .or_else(|| rest.strip_suffix(':').map(|k| k.len()))?,
+    };
+    let colon = column + key_end;
+    if !text[colon..].starts_with(':') {
+        return None;
+    }
+
+    let after = &text[colon + 1..];
+    let start = colon + 1 + (after.len() - after.trim_start().len());
+    let value = &text[start..];
+    if value.starts_with('#') {
+        return Some((colon, start, start));
+    }
+
+    let mut end = value.len();
+    let mut quote: Option<char> = None;
+    let mut previous = ' ';
+    for (i, c) in value.char_indices() {
+        match quote {
+            Some('"') if c == '"' && previous != '\\' => quote = None,
+            Some('\'') if c == '\'' => quote = None,
+            Some(_) => {}
+            None if (c == '"' || c == '\'') && i == 0 => quote = Some(c),
+            None if c == '#' && previous.is_whitespace() => {
+                end = i;
+                break;
+            }
+            None => {}
+        }
+        previous = c;
+    }
+    Some((colon, start, start + value[..end].trim_end().len()))
+}
+
+/// Index of the first line after the block started by a key.
+///
+/// Trailing blank lines and comments are not part of the block.
+fn block_end(lines: &[String], line: usize, column: usize) -> usize {
+    let mut end = line + 1;
+    for (i, text) in lines.iter().enumerate().skip(line + 1) {
+        let trimmed = text.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let indent = text.len() - trimmed.len();
+        let is_item = trimmed == "-" || trimmed.starts_with("- ");
+        if indent > column || (indent == column && is_item) {
+            end = i + 1;
+            continue;
+        }
+        break;
+    }
+    end
+}
+
+/// Render a key and its value as block-style YAML lines.
+///
+/// Nested values are indented by `step` spaces per level.
+fn render(prefix: &str, value: &Value, child_indent: usize, step: usize) -> Vec<String> {
+    let rendered = serde_yaml::to_string(value).unwrap_or_default();
+    let mut rendered = rendered.lines().map(|line| {
+        // `serde_yaml` always indents with two spaces.
+        let trimmed = line.trim_start();
+        let level = (line.len() - trimmed.len()) / 2;
+        format!("{}{}", " ".repeat(level * step), trimmed)
+    });
+    let padding = " ".repeat(child_indent);
+
+    let mut lines: Vec<String> = Vec::new();
+    let is_block = match value {
+        Value::Mapping(map) => !map.is_empty(),
+        Value::Sequence(seq) => !seq.is_empty(),
+        _ => false,
+    };
+    if is_block {
+        lines.push(prefix.to_string());
+    } else {
+        lines.push(format!(
+            "{} {}",
+            prefix,
+            rendered.next().unwrap_or_default()
+        ));
+    }
+    lines.extend(rendered.map(|line| format!("{}{}", padding, line)));
+    lines
+}
+
+/// Render a mapping key, quoting it if needed.
+fn render_key(key: &str) -> String {
+    serde_yaml::to_string(&Value::String(key.to_string()))
+        .unwrap_or_default()
+        .trim_end()
+        .to_string()
+}
+
+/// Turn a mapping key into a path segment.
+pub(crate) fn segment(key: &Value) -> String {
+    match key {
+        Value::String(key) => key.clone(),
+        other => serde_yaml::to_string(other)
+            .unwrap_or_default()
+            .trim()
+            .to_string(),
+    }
+}
+
+/// Get a value by path. Sequence items are addressed by index.
+pub(crate) fn get<'a>(value: &'a Value, path: &[String]) -> Option<&'a Value> {
+    path.iter().try_fold(value, |value, segment| match value {
+        Value::Mapping(map) => map.get(segment.as_str()),
+        Value::Sequence(seq) => seq.get(segment.parse::<usize>().ok()?),
+        _ => None,
+    })
+}
+
+pub(crate) fn get_mut<'a>(value: &'a mut Value, path: &[String]) -> Option<&'a mut Value> {
+    path.iter().try_fold(value, |value, segment| match value {
+        Value::Mapping(map) => map.get_mut(segment.as_str()),
+        Value::Sequence(seq) => seq.get_mut(segment.parse::<usize>().ok()?),
+        _ => None,
+    })
+}
+
+/// Set a value, appending it if the path points right past the end of a sequence.
+pub(crate) fn set(root: &mut Value, path: &[String], value: Value) -> bool {
+    let Some((last, parent)) = path.split_last() else {
+        *root = value;
+        return true;
+    };
+    match get_mut(root, parent) {
+        Some(Value::Mapping(map)) => {
+            map.insert(Value::String(last.clone()), value);
+            true
+        }
+        Some(Value::Sequence(seq)) => match last.parse::<usize>() {
+            Ok(index) if index < seq.len() => {
+                seq[index] = value;
+                true
+            }
+            Ok(index) if index == seq.len() => {
+                seq.push(value);
+                true
+            }
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// Set a value, creating missing parent mappings.
+pub(crate) fn insert(root: &mut Value, path: &[String], value: Value) -> bool {
+    for depth in 1..path.len() {
+        if get(root, &path[..depth]).is_none_or(Value::is_null)
+            && !set(root, &path[..depth], Value::Mapping(Default::default()))
+        {
+            return false;
+        }
+    }
+    set(root, path, value)
+}
+
+/// List the paths of every leaf value. Sequences are leaves.
+pub(crate) fn leaves(value: &Value) -> Vec<Vec<String>> {
+    let Value::Mapping(map) = value else {
+        return Vec::from([Vec::new()]);
+    };
+    let mut paths: Vec<Vec<String>> = Vec::new();
+    for (key, value) in map {
+        for mut path in leaves(value) {
+            path.insert(0, segment(key));
+            paths.push(path);
+        }
+    }
+    paths
+}
+
+/// Remove a mapping key or sequence item.
+pub(crate) fn remove(root: &mut Value, path: &[String]) -> bool {
+    let Some((last, parent)) = path.split_last() else {
+        return false;
+    };
+    match get_mut(root, parent) {
+        Some(Value::Mapping(map)) => map.remove(last.as_str()).is_some(),
+        Some(Value::Sequence(seq)) => match last.parse::<usize>() {
+            Ok(index) if index < seq.len() => {
+                seq.remove(index);
+                true
+            }
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// A change to apply to a document.
+enum Edit {
+    Set(Vec<String>, Value),
+    Remove(Vec<String>),
+}
+
+/// List the changes between two values. Only mappings are compared key by key.
+fn diff(path: &mut Vec<String>, old: Option<&Value>, new: &Value, edits: &mut Vec<Edit>) {
+    match (old, new) {
+        (Some(Value::Mapping(old)), Value::Mapping(new)) => {
+            for (key, value) in new {
+                path.push(segment(key));
+                diff(path, old.get(key), value, edits);
+                path.pop();
+            }
+            for key in old.keys().filter(|key| !new.contains_key(*key)) {
+                path.push(segment(key));
+                edits.push(Edit::Remove(path.clone()));
+                path.pop();
+            }
+        }
+        (Some(old), new) if old == new => {}
+        (_, new) => edits.push(Edit::Set(path.clone(), new.clone())),
+    }
+}
+
+/// Column of the first child of a node, if any.
+fn child_column(nodes: &[Node], node: &Node) -> Option<usize> {
+    let prefix = format!("{}.", node.path);
+    nodes
+        .iter()
+        .find(|n| n.line > node.line && n.path.starts_with(&prefix))
+        .map(|n| n.column)
+}
+
+/// Indentation used by the document, defaulting to two spaces.
+fn indent_step(nodes: &[Node]) -> usize {
+    nodes
+        .iter()
+        .filter(|n| n.column > 0)
+        .map(|n| n.column)
+        .min()
+        .unwrap_or(2)
+}
+
+/// Set a value in the document, inserting missing keys at the end of their parent.
+fn apply_set(
+    lines: &mut Vec<String>,
+    path: &[String],
+    value: &Value,
+    root: &Value,
+) -> Option<()> {
+    let nodes = nodes(&lines.join("\n"));
+    let find = |path: &[String]| nodes.iter().find(|n| n.path == path.join("."));
+    let step = indent_step(&nodes);
+
+    if let Some(node) = find(path) {
+        let text = &lines[node.line];
+        let (colon, start, end) = value_span(text, node.column)?;
+        let comment = text[end..].to_string();
+        let indent = child_column(&nodes, node).unwrap_or(node.column + step);
+        let end_line = block_end(lines, node.line, node.column);
+
+        let mut new_lines = render(&text[..=colon], value, indent, step);
+        if new_lines.len() == 1 && start != end {
+            new_lines[0].push_str(&comment);
+        }
+        lines.splice(node.line..end_line, new_lines);
+        return Some(());
+    }
+
+    // Find the closest existing parent.
+    let mut depth = path.len() - 1;
+    while depth > 0 && find(&path[..depth]).is_none() {
+        depth -= 1;
+    }
+    let (position, indent) = match depth {
+        0 => (lines.len(), 0),
+        _ => {
+            let parent = find(&path[..depth])?;
+            let (_, start, end) = value_span(&lines[parent.line], parent.column)?;
+            if start != end {
+                // The parent has an inline value, such as `{}`, so replace it as a whole.
+                return apply_set(lines, &path[..depth], get(root, &path[..depth])?, root);
+            }
+            let indent = child_column(&nodes, parent).unwrap_or(parent.column + step);
+            (block_end(lines, parent.line, parent.column), indent)
+        }
+    };
+
+    let nested = path[depth + 1..]
+        .iter()
+        .rev()
+        .fold(value.clone(), |v, key| {
+            let mut map = Mapping::new();
+            map.insert(Value::String(key.clone()), v);
+            Value::Mapping(map)
+        });
+    let prefix = format!("{}{}:", " ".repeat(indent), render_key(&path[depth]));
+    lines.splice(
+        position..position,
+        render(&prefix, &nested, indent + step, step),
+    );
+    Some(())
+}
+
+/// Update a YAML document to match `new`, editing only keys that differ from `old`.
+///
+/// Comments, ordering, and keys missing from both values are kept.
+/// Returns `None` if the document can't be edited in place.
+pub(crate) fn update(contents: &str, old: &Value, new: &Value) -> Option<String> {
+    let mut edits: Vec<Edit> = Vec::new();
+    diff(&mut Vec::new(), Some(old), new, &mut edits);
+
+    let mut lines: Vec<String> = contents.lines().map(str::to_string).collect();
+    for edit in edits {
+        match edit {
+            Edit::Set(path, value) => apply_set(&mut lines, &path, &value, new)?,
+            Edit::Remove(path) => {
+                let nodes = nodes(&lines.join("\n"));
+                if let Some(node) = nodes.iter().find(|n| n.path == path.join(".")) {
+                    let end_line = block_end(&lines, node.line, node.column);
+                    lines.drain(node.line..end_line);
+                }
+            }
+        }
+    }
+
+    let mut updated = lines.join("\n");
+    updated.push('\n');
+    Some(updated)
+}
@@ -7,12 +7,15 @@
 //!
 //! - ~`user` will expand to the user's home directory from the user database
 //!
+//! Paths can also be collapsed back into the `~` form, to make them portable.
+//!
 //! Example:
 //!
 //! ```
 //! use homedir::HomeDirExt;
 //!
 //! let public_html = "~/public_html".expand_home().unwrap();
+//! assert_eq!(public_html.collapse_home(), std::path::PathBuf::from("~/public_html"));
 //! ```
 
 use home::home_dir;
@@ -63,13 +66,30 @@ pub trait HomeDirExt {
     /// let public_html = "~/public_html".expand_home().unwrap();
     ///
     /// // Resolves to `/home/john_doe/public_html`,
-    /// // if john_doe exists as a user
-    /// let john_doe_html = "~john_doe/public_html".expand_home().unwrap();
+    /// // if john_doe exists as a user, and fails otherwise
+    /// let john_doe_html = "~john_doe/public_html".expand_home();
     ///
     /// // Resolves to /root
+    /// # #[cfg(not(target_os = "windows"))]
     /// let root_home = "~root".expand_home().unwrap();
     /// ```
     fn expand_home(&self) -> Result<PathBuf, Error>;
+
+    /// Replaces the current user's home directory at the start of a path with a tilde.
+    ///
+    /// Paths outside the home directory are returned as-is.
+    ///
+    /// Examples:
+    /// ```
+    /// # use homedir::HomeDirExt;
+    /// # use std::path::PathBuf;
+    /// let vimrc = "~/.vimrc".expand_home().unwrap();
+    ///
+    /// assert_eq!(vimrc.collapse_home(), PathBuf::from("~/.vimrc"));
+    /// # #[cfg(not(target_os = "windows"))]
+    /// assert_eq!("/etc/hosts".collapse_home(), PathBuf::from("/etc/hosts"));
+    /// ```
+    fn collapse_home(&self) -> PathBuf;
 }
 
 impl HomeDirExt for Path {
@@ -106,6 +126,19 @@ impl HomeDirExt for Path {
 
         Ok(path)
     }
+
+    fn collapse_home(&self) -> PathBuf {
+        let home = getenv().or_else(|| getent_current().ok());
+        // A home directory at the root would match every path.
+        let Some(home) = home.filter(|home| home.parent().is_some()) else {
+            return self.to_path_buf();
+        };
+        match self.strip_prefix(&home) {
+            Ok(rest) if rest.as_os_str().is_empty() => PathBuf::from("~"),
+            Ok(rest) => Path::new("~").join(rest),
+            Err(_) => self.to_path_buf(),
+        }
+    }
 }
 
 impl<T> HomeDirExt for T
@@ -115,10 +148,14 @@ where
     fn expand_home(&self) -> Result<PathBuf, Error> {
         self.as_ref().expand_home()
     }
+
+    fn collapse_home(&self) -> PathBuf {
+        self.as_ref().collapse_home()
+    }
 }
 
 pub(crate) fn getenv() -> Option<PathBuf> {
-    home_dir().map(Into::into)
+    home_dir()
 }
 
 #[cfg(not(target_os = "windows"))]
@@ -33,6 +33,14 @@ fn test_expand() {
 
     #[cfg(target_os = "windows")]
     assert_eq!(r"~\.vimrc".expand_home().unwrap(), subpath);
+
+    // Collapsing is the reverse of expanding.
+    assert_eq!(subpath.collapse_home(), Path::new("~").join(".vimrc"));
+    assert_eq!(homepath.collapse_home(), PathBuf::from("~"));
+    assert_eq!(
+        homepath.with_file_name("other").collapse_home(),
+        homepath.with_file_name("other")
+    );
 }
 
 /// Test that paths without `~` are returned as-is.
@@ -0,0 +1,376 @@
+//! Cross-check local assets against the `resource` table.
+//!
+//! Finds files in the Memos assets directory that are no longer referenced by any
+//! resource (orphans), and resources whose local file has vanished (missing).
+//!
+//! Notes:
+//! - Nothing is ever deleted. Orphans can optionally be moved to a quarantine folder.
+//! - Recently modified files are never quarantined, as Memos may still be
+//!   recording their resource.
+//! - Only resources using the `LOCAL` storage type are checked.
+//! - Valid from Memos v0.22.0 onwards.
+
+use log::{debug, info, warn};
+use sea_orm::IdenStatic;
+use sea_orm::*;
+use sea_orm_migration::prelude::*;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::env::consts::OS;
+use std::fs;
+use std::io::{Error, ErrorKind, Result as IoResult};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::m20240522_000002_migrate_resource_paths::resource;
+use crate::resource_path;
+use resource::Entity as Resource;
+
+/// Name of the directory where Memos stores local assets.
+pub const ASSETS_DIR: &str = "assets";
+
+/// Orphaned files modified less than this long before the scan are left in place.
+///
+/// Memos writes an uploaded file before its resource, so a file uploaded
+/// around the scan may look orphaned while it isn't.
+pub const QUARANTINE_MIN_AGE: Duration = Duration::from_secs(5 * 60);
+
+/// A file under the assets directory without a matching `resource.reference`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct OrphanedAsset {
+    /// Absolute file path.
+    pub path: PathBuf,
+    /// File size, in bytes.
+    pub size: u64,
+}
+
+/// A resource whose referenced file does not exist.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MissingAsset {
+    /// Resource id.
+    pub id: i32,
+    /// Original file name, as uploaded.
+    pub filename: String,
+    /// Stored `resource.reference`.
+    pub reference: String,
+    /// Resolved absolute file path.
+    pub path: PathBuf,
+    /// File size recorded in the database, in bytes.
+    pub size: u64,
+}
+
+/// Result of an asset scan.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AssetReport {
+    /// Files not referenced by any resource.
+    pub orphaned: Vec<OrphanedAsset>,
+    /// Resources whose file is gone.
+    pub missing: Vec<MissingAsset>,
+    /// When the scan started.
+    #[serde(skip)]
+    pub scanned_at: SystemTime,
+}
+
+impl Default for AssetReport {
+    fn default() -> Self {
+        Self {
+            orphaned: Vec::new(),
+            missing: Vec::new(),
+            scanned_at: SystemTime::now(),
+        }
+    }
+}
+
+impl AssetReport {
+    /// Total size of orphaned files, in bytes.
+    pub fn orphaned_size(&self) -> u64 {
+        self.orphaned.iter().map(|a| a.size).sum()
+    }
+
+    /// Total size of missing files, as recorded in the database, in bytes.
+    pub fn missing_size(&self) -> u64 {
+        self.missing.iter().map(|a| a.size).sum()
+    }
+}
+
+/// Resolve a `resource.reference` to an absolute path.
+///
+/// Relative references are resolved against the Memos data directory.
+pub fn resolve_reference(data_dir: &Path, reference: &str) -> PathBuf {
+    let path = Path::new(reference);
+    if path.is_absolute() {
+        return path.to_path_buf();
+    }
+    data_dir.join(resource_path::to_slash(reference))
+}
+
+/// Normalize a path so it can be compared regardless of separators and case on Windows.
+fn comparable(path: &Path) -> String {
+    let slashed = resource_path::to_slash(&path.to_string_lossy());
+    if OS == "windows" {
+        return slashed.to_lowercase();
+    }
+    slashed
+}
+
+/// Recursively list all files under `dir`.
+fn list_files(dir: &Path) -> IoResult<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = Vec::new();
+    if !dir.is_dir() {
+        return Ok(files);
+    }
+
+    let mut pending: Vec<PathBuf> = Vec::from([dir.to_path_buf()]);
+    while let Some(current) = pending.pop() {
+        for entry in fs::read_dir(&current)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                pending.push(path);
+            } else if path.is_file() {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Scan the Memos data directory for orphaned and missing assets.
+///
+/// Returns an empty report if the database schema is not supported.
+pub async fn scan(db: &DatabaseConnection, data_dir: &Path) -> Result<AssetReport, DbErr> {
+    let scanned_at = SystemTime::now();
+    let manager = SchemaManager::new(db);
+
+    // Check the `resource` table schema.
+    {
+        if !manager.has_table(Resource.table_name()).await? {
+            return Ok(AssetReport::default()); // Schema not supported.
+        }
+
+        for column in [
+            resource::Column::Id,
+            resource::Column::Filename,
+            resource::Column::Size,
+            resource::Column::Blob,
+            resource::Column::StorageType,
+            resource::Column::Reference,
+        ] {
+            if !manager
+                .has_column(Resource.table_name(), column.as_str())
+                .await?
+            {
+                return Ok(AssetReport::default()); // Schema not supported.
+            }
+        }
+    }
+
+    let resources = Resource::find()
+        .select_only()
+        .columns([
+            resource::Column::Id,
+            resource::Column::Filename,
+            resource::Column::Size,
+            resource::Column::Reference,
+        ])
+        .filter(
+            Condition::all()
+                .add(resource::Column::StorageType.eq("LOCAL"))
+                .add(resource::Column::Blob.is_null())
+                .add(resource::Column::Reference.ne(""))
+                .add(
+                    Condition::all()
+                        .add(resource::Column::Reference.starts_with("http"))
+                        .not(),
+                ),
+        )
+        .into_tuple::<(i32, String, i32, String)>()
+        .all(db)
+        .await?;
+    debug!("Found {} local resources.", resources.len());
+
+    let mut report = AssetReport {
+        scanned_at,
+        ..Default::default()
+    };
+    let mut referenced: HashSet<String> = HashSet::with_capacity(resources.len());
+    for (id, filename, size, reference) in resources {
+        let path = resolve_reference(data_dir, &reference);
+        referenced.insert(comparable(&path));
+        if !path.is_file() {
+            report.missing.push(MissingAsset {
+                id,
+                filename,
+                reference,
+                path,
+                size: size.max(0) as u64,
+            });
+        }
+    }
+
+    let assets_dir = data_dir.join(ASSETS_DIR);
+    let files = list_files(&assets_dir).map_err(|e| DbErr::Custom(e.to_string()))?;
+    for path in files {
+        if referenced.contains(&comparable(&path)) {
+            continue;
+        }
+        let size = fs::metadata(&path).map(|m| m.len()).unwrap_or_default();
+        report.orphaned.push(OrphanedAsset { path, size });
+    }
+
+    info!(
+        "Asset scan: {} orphaned file(s) ({} bytes), {} missing file(s) ({} bytes).",
+        report.orphaned.len(),
+        report.orphaned_size(),
+        report.missing.len(),
+        report.missing_size()
+    );
+    Ok(report)
+}
+
+/// Rewrite absolute `resource.reference` paths as paths relative to the data directory.
+///
+/// Only references under `previous_data_dir` are converted, as the files elsewhere
+/// aren't moved. Used when relocating the Memos data directory, so assets keep
+/// working from the new location.
+///
+/// Returns the amount of updated resources.
+pub async fn relativize_references(
+    db: &DatabaseConnection,
+    previous_data_dir: &Path,
+) -> Result<usize, DbErr> {
+    let manager = SchemaManager::new(db);
+    if !manager.has_table(Resource.table_name()).await?
+        || !manager
+            .has_column(Resource.table_name(), resource::Column::Reference.as_str())
+            .await?
+    {
+        return Ok(0); // Schema not supported.
+    }
+
+    let resources = Resource::find()
+        .select_only()
+        .columns([resource::Column::Id, resource::Column::Reference])
+        .filter(
+            Condition::all()
+                .add(resource::Column::StorageType.eq("LOCAL"))
+                .add(resource::Column::Reference.ne(""))
+                .add(
+                    Condition::any()
+                        .add(resource::Column::Reference.starts_with("assets/"))
+                        .add(resource::Column::Reference.starts_with("http"))
+                        .not(),
+                ),
+        )
+        .into_tuple::<(i32, String)>()
+        .all(db)
+        .await?;
+
+    let previous_data_dir = previous_data_dir.to_string_lossy();
+    let mut updated = 0;
+    let transaction = db.begin().await?;
+    for (id, reference) in resources {
+        if !Path::new(&reference).is_absolute() {
+            continue;
+        }
+        let Some(new_path) = resource_path::relativize_under(&reference, &previous_data_dir)
+        else {
+            continue;
+        };
+
+        Resource::update_many()
+            .col_expr(resource::Column::Reference, Expr::value(&new_path))
+            .filter(resource::Column::Id.eq(id))
+            .exec(&transaction)
+            .await?;
+        debug!("Relocated resource {}: {} => {}", id, reference, new_path);
+        updated += 1;
+    }
+    transaction.commit().await?;
+
+    info!("Rewrote {} absolute resource reference(s).", updated);
+    Ok(updated)
+}
+
+/// Move orphaned assets to a quarantine folder, keeping their relative paths.
+///
+/// Files modified after the scan, or less than [`QUARANTINE_MIN_AGE`] before it,
+/// are skipped, so uploads made while Memos is running are never moved away.
+/// Existing files in the quarantine folder are never overwritten.
+/// Returns the new location of each moved file.
+pub fn quarantine(
+    report: &AssetReport,
+    data_dir: &Path,
+    quarantine_dir: &Path,
+) -> IoResult<Vec<PathBuf>> {
+    if quarantine_dir.starts_with(data_dir.join(ASSETS_DIR)) {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "quarantine folder must not be inside the assets directory",
+        ));
+    }
+
+    let cutoff = report
+        .scanned_at
+        .checked_sub(QUARANTINE_MIN_AGE)
+        .unwrap_or(SystemTime::UNIX_EPOCH);
+    let mut moved: Vec<PathBuf> = Vec::with_capacity(report.orphaned.len());
+    for orphan in &report.orphaned {
+        let modified = fs::metadata(&orphan.path).and_then(|m| m.modified());
+        match modified {
+            Ok(modified) if modified <= cutoff => {}
+            Ok(_) => {
+                info!(
+                    "Skipped recently modified `{}`.",
+                    orphan.path.to_string_lossy()
+                );
+                continue;
+            }
+            // Gone since the scan.
+            Err(_) => continue,
+        }
+
+        let relative = orphan
+            .path
+            .strip_prefix(data_dir)
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|_| PathBuf::from(orphan.path.file_name().unwrap_or_default()));
+
+        let mut target = quarantine_dir.join(&relative);
+        let mut count = 1;
+        while target.exists() {
+            let name = relative.file_name().unwrap_or_default().to_string_lossy();
+            target.set_file_name(format!("{}.{}", name, count));
+            count += 1;
+        }
+
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        // Rename fails across file systems, so fall back to copy and remove.
+        if fs::rename(&orphan.path, &target).is_err() {
+            fs::copy(&orphan.path, &target)?;
+            if let Err(e) = fs::remove_file(&orphan.path) {
+                warn!(
+                    "Failed to remove `{}` after quarantine: {}",
+                    orphan.path.to_string_lossy(),
+                    e
+                );
+            }
+        }
+        debug!(
+            "Quarantined `{}` => `{}`",
+            orphan.path.to_string_lossy(),
+            target.to_string_lossy()
+        );
+        moved.push(target);
+    }
+
+    info!(
+        "Moved {} orphaned file(s) to `{}`.",
+        moved.len(),
+        quarantine_dir.to_string_lossy()
+    );
+    Ok(moved)
+}
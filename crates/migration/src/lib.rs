@@ -1,9 +1,12 @@
 pub use sea_orm_migration::prelude::*;
 
+pub mod assets;
 mod m20220220_000001_migrate_resource_paths;
 mod m20240522_000002_migrate_resource_paths;
 mod m20240525_000001_storage_settings;
 mod resource_path;
+#[cfg(test)]
+mod tests;
 
 pub struct Migrator;
 
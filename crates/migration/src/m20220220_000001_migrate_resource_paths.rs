@@ -12,6 +12,7 @@
 //! - As of sea-orm 0.12.15 it's not possible to rename a previous migration without breaking all migrator functionality.
 
 use log::{debug, info, LevelFilter};
+use sea_orm::IdenStatic;
 use sea_orm::*;
 use sea_orm_migration::prelude::*;
 
@@ -104,24 +105,7 @@ impl MigrationTrait for Migration {
         let mut migrated_count = 0;
         let transaction = db.begin().await?;
         for resource in resources {
-            let mut new_path = resource.internal_path.clone();
-
-            // Strip known path prefixes.
-            for p in &paths {
-                new_path = new_path.trim_start_matches(p).to_string();
-            }
-
-            new_path = resource_path::to_slash(&new_path);
-
-            // Fall back: strip everything before "/assets/".
-            if new_path.contains("/assets/") {
-                if let Some(file_name) = new_path.split("/assets/").collect::<Vec<&str>>().pop()
-                {
-                    new_path = "assets/".to_string() + file_name;
-                }
-            }
-
-            new_path = new_path.trim_start_matches('/').to_string();
+            let new_path = resource_path::relativize(&resource.internal_path, &paths);
 
             // Update only if the path has changed.
             if new_path != resource.internal_path {
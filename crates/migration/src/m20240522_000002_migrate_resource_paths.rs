@@ -10,11 +10,16 @@
 //! - Valid from Memos v0.22.0 onwards.
 
 use log::{debug, info, LevelFilter};
+use sea_orm::IdenStatic;
 use sea_orm::*;
 use sea_orm_migration::prelude::*;
 
 use crate::resource_path::{self};
-mod resource {
+
+/// `resource` entity, as of Memos v0.22.0.
+///
+/// Also used by the [`assets`](crate::assets) scanner.
+pub(crate) mod resource {
     use sea_orm::entity::prelude::*;
     #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
     #[sea_orm(table_name = "resource")]
@@ -105,24 +110,7 @@ impl MigrationTrait for Migration {
         let mut migrated_count = 0;
         let transaction = db.begin().await?;
         for resource in resources {
-            let mut new_path = resource.reference.clone();
-
-            // Strip known path prefixes.
-            for p in &paths {
-                new_path = new_path.trim_start_matches(p).to_string();
-            }
-
-            new_path = resource_path::to_slash(&new_path);
-
-            // Fall back: strip everything before "/assets/".
-            if new_path.contains("/assets/") {
-                if let Some(file_name) = new_path.split("/assets/").collect::<Vec<&str>>().pop()
-                {
-                    new_path = "assets/".to_string() + file_name;
-                }
-            }
-
-            new_path = new_path.trim_start_matches('/').to_string();
+            let new_path = resource_path::relativize(&resource.reference, &paths);
 
             // Update only if the path has changed.
             if new_path != resource.reference {
@@ -75,3 +75,55 @@ pub fn build_path_list() -> Vec<String> {
     }
     paths.into_iter().unique().collect()
 }
+
+/// Convert an absolute resource path to a path relative to the Memos data directory.
+///
+/// Strips the supplied path prefixes (see [`build_path_list`]) and, as a fall back,
+/// everything before "/assets/". Relative paths are returned with forward slashes.
+///
+/// Only meant for the path migrations: the fall back also rewrites paths outside
+/// the data directory. See [`relativize_under`] for a strict version.
+pub fn relativize(path: &str, prefixes: &[String]) -> String {
+    let mut new_path = path.to_string();
+
+    // Strip known path prefixes.
+    for p in prefixes {
+        new_path = new_path.trim_start_matches(p).to_string();
+    }
+
+    new_path = to_slash(&new_path);
+
+    // Fall back: strip everything before "/assets/".
+    if new_path.contains("/assets/") {
+        if let Some(file_name) = new_path.split("/assets/").collect::<Vec<&str>>().pop() {
+            new_path = "assets/".to_string() + file_name;
+        }
+    }
+
+    new_path.trim_start_matches('/').to_string()
+}
+
+/// Convert an absolute resource path under `dir` to a path relative to `dir`,
+/// with forward slashes.
+///
+/// Returns None for paths outside `dir`. Paths are compared regardless of
+/// separators, and regardless of case on Windows.
+pub fn relativize_under(path: &str, dir: &str) -> Option<String> {
+    let comparable = |p: &str| {
+        let slashed = to_slash(p);
+        if OS == "windows" {
+            return slashed.to_lowercase();
+        }
+        slashed
+    };
+    let prefix = comparable(&norm_suffix(&to_slash(dir)));
+    if prefix.is_empty() || !comparable(path).starts_with(&prefix) {
+        return None;
+    }
+    let relative = to_slash(path)
+        .chars()
+        .skip(prefix.chars().count())
+        .collect::<String>();
+    let relative = relative.trim_start_matches('/').to_string();
+    (!relative.is_empty()).then_some(relative)
+}
@@ -0,0 +1,218 @@
+#[cfg(test)]
+use {
+    crate::assets::{self, AssetReport, OrphanedAsset, QUARANTINE_MIN_AGE},
+    crate::resource_path,
+    sea_orm::{ConnectionTrait, Database, DatabaseConnection, DbBackend, Statement},
+    std::fs,
+    std::path::Path,
+    std::time::{Duration, SystemTime},
+};
+
+/// Connect to an in-memory database with the Memos v0.22 `resource` table.
+async fn resource_db(references: &[&str]) -> DatabaseConnection {
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    db.execute_unprepared(
+        "CREATE TABLE resource (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            uid TEXT NOT NULL DEFAULT '',
+            creator_id INTEGER NOT NULL DEFAULT 1,
+            created_ts BIGINT NOT NULL DEFAULT 0,
+            updated_ts BIGINT NOT NULL DEFAULT 0,
+            filename TEXT NOT NULL DEFAULT '',
+            blob BLOB DEFAULT NULL,
+            type TEXT NOT NULL DEFAULT '',
+            size INTEGER NOT NULL DEFAULT 0,
+            memo_id INTEGER,
+            storage_type TEXT NOT NULL DEFAULT '',
+            reference TEXT NOT NULL DEFAULT '',
+            payload TEXT NOT NULL DEFAULT '{}'
+        );",
+    )
+    .await
+    .unwrap();
+    for reference in references {
+        db.execute_unprepared(&format!(
+            "INSERT INTO resource (filename, size, storage_type, reference) \
+            VALUES ('{0}', 3, 'LOCAL', '{0}');",
+            reference
+        ))
+        .await
+        .unwrap();
+    }
+    db
+}
+
+/// Write a file, creating its parent directories.
+fn write_file(path: &Path) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, "abc").unwrap();
+}
+
+/// Set the modification time of a file to `age` ago.
+fn set_age(path: &Path, age: Duration) {
+    let file = fs::File::options().write(true).open(path).unwrap();
+    file.set_modified(SystemTime::now() - age).unwrap();
+}
+
+#[tokio::test]
+async fn test_scan() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let data_dir = tmp_dir.path();
+    write_file(&data_dir.join("assets/kept.png"));
+    write_file(&data_dir.join("assets/2024/orphan.png"));
+    let absolute = data_dir.join("assets/absolute.png");
+    write_file(&absolute);
+
+    let absolute_reference = absolute.to_string_lossy().to_string();
+    let db = resource_db(&[
+        "assets/kept.png",
+        "assets/gone.png",
+        &absolute_reference,
+        "https://example.com/remote.png",
+    ])
+    .await;
+
+    let report = assets::scan(&db, data_dir).await.unwrap();
+    assert_eq!(
+        report.orphaned,
+        vec![OrphanedAsset {
+            path: data_dir.join("assets/2024/orphan.png"),
+            size: 3,
+        }]
+    );
+    assert_eq!(report.missing.len(), 1);
+    assert_eq!(report.missing[0].reference, "assets/gone.png");
+    assert_eq!(report.missing[0].path, data_dir.join("assets/gone.png"));
+    assert_eq!(report.missing_size(), 3);
+
+    // Unsupported schemas give an empty report.
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    let report = assets::scan(&db, data_dir).await.unwrap();
+    assert!(report.orphaned.is_empty() && report.missing.is_empty());
+
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    db.execute_unprepared("CREATE TABLE resource (id INTEGER PRIMARY KEY, reference TEXT);")
+        .await
+        .unwrap();
+    let report = assets::scan(&db, data_dir).await.unwrap();
+    assert!(report.orphaned.is_empty() && report.missing.is_empty());
+}
+
+#[test]
+fn test_quarantine() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let data_dir = tmp_dir.path();
+    let quarantine_dir = data_dir.join("quarantine");
+    let old = data_dir.join("assets/2024/old.png");
+    let recent = data_dir.join("assets/recent.png");
+    let existing = quarantine_dir.join("assets/2024/old.png");
+    write_file(&old);
+    write_file(&recent);
+    write_file(&existing);
+    set_age(&old, QUARANTINE_MIN_AGE * 2);
+
+    let report = AssetReport {
+        orphaned: [&old, &recent, &data_dir.join("assets/vanished.png")]
+            .into_iter()
+            .map(|path| OrphanedAsset {
+                path: path.clone(),
+                size: 3,
+            })
+            .collect(),
+        ..Default::default()
+    };
+
+    // Recently modified and vanished files are skipped, existing files are kept.
+    let moved = assets::quarantine(&report, data_dir, &quarantine_dir).unwrap();
+    assert_eq!(moved, vec![quarantine_dir.join("assets/2024/old.png.1")]);
+    assert!(!old.exists());
+    assert!(recent.exists());
+    assert!(existing.exists());
+
+    // The quarantine folder can't be inside the assets directory.
+    assert!(assets::quarantine(&report, data_dir, &data_dir.join("assets/q")).is_err());
+}
+
+#[test]
+fn test_relativize() {
+    let prefixes = Vec::from(["/var/opt/memos/".to_string()]);
+    assert_eq!(
+        resource_path::relativize("/var/opt/memos/assets/a.png", &prefixes),
+        "assets/a.png"
+    );
+    // The migrations fall back to everything after "/assets/".
+    assert_eq!(
+        resource_path::relativize("/elsewhere/assets/b.png", &prefixes),
+        "assets/b.png"
+    );
+    assert_eq!(
+        resource_path::relativize(r"C:\memos\assets\c.png", &[r"C:\memos\".to_string()]),
+        "assets/c.png"
+    );
+}
+
+#[test]
+fn test_relativize_under() {
+    let dir = "/home/user/.memospot";
+    assert_eq!(
+        resource_path::relativize_under("/home/user/.memospot/assets/2024/a.png", dir),
+        Some("assets/2024/a.png".to_string())
+    );
+    assert_eq!(
+        resource_path::relativize_under(
+            "/home/user/.memospot/assets/a.png",
+            "/home/user/.memospot/"
+        ),
+        Some("assets/a.png".to_string())
+    );
+    // Paths outside the directory are left alone, even under an `assets` folder.
+    assert_eq!(
+        resource_path::relativize_under("/media/photos/assets/a.png", dir),
+        None
+    );
+    assert_eq!(
+        resource_path::relativize_under("/home/user/.memospot-old/assets/a.png", dir),
+        None
+    );
+    assert_eq!(resource_path::relativize_under(dir, dir), None);
+    assert_eq!(
+        resource_path::relativize_under(r"C:\Memos\assets\a.png", r"C:\Memos"),
+        Some("assets/a.png".to_string())
+    );
+}
+
+#[tokio::test]
+async fn test_relativize_references() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let previous = tmp_dir.path().join("previous");
+    let inside = previous.join("assets/a.png").to_string_lossy().to_string();
+    let outside = tmp_dir
+        .path()
+        .join("photos/assets/b.png")
+        .to_string_lossy()
+        .to_string();
+    let db = resource_db(&[&inside, &outside, "assets/c.png"]).await;
+
+    let updated = assets::relativize_references(&db, &previous).await.unwrap();
+    assert_eq!(updated, 1);
+
+    let rows = db
+        .query_all(Statement::from_string(
+            DbBackend::Sqlite,
+            "SELECT reference FROM resource ORDER BY id;",
+        ))
+        .await
+        .unwrap();
+    let references: Vec<String> = rows
+        .iter()
+        .map(|row| row.try_get_by_index::<String>(0).unwrap())
+        .collect();
+    assert_eq!(
+        references,
+        vec![
+            "assets/a.png".to_string(),
+            outside,
+            "assets/c.png".to_string()
+        ]
+    );
+}
@@ -0,0 +1,207 @@
+//! Command-line arguments.
+
+use crate::init;
+use config::env::{ENV_PREFIX, ENV_SEPARATOR};
+use config::{Config, ConfigLayers};
+use std::path::PathBuf;
+
+/// Commands run instead of the app.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    /// `config env`: list the environment variables overriding settings.
+    ConfigEnv,
+    /// `config show`: list the effective settings.
+    ConfigShow {
+        /// `--origin`: tell where each value comes from.
+        origin: bool,
+        /// `--diff`: only list the settings that differ from the defaults.
+        diff: bool,
+    },
+}
+
+/// Options passed on the command line.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Args {
+    /// Configuration file to use instead of the one in the data directory.
+    pub config: Option<PathBuf>,
+    /// Configuration profile to use instead of `MEMOSPOT_PROFILE`.
+    pub profile: Option<String>,
+    /// Command to run instead of the app.
+    pub command: Option<Command>,
+}
+
+impl Args {
+    /// Parse the arguments of the current process.
+    pub fn parse() -> Self {
+        Self::parse_from(std::env::args().skip(1))
+    }
+
+    /// Parse arguments, in either `--name value` or `--name=value` form,
+    /// followed by an optional command, such as `config env`.
+    ///
+    /// Unknown arguments are ignored, as the OS may pass its own. An option
+    /// followed by another option instead of its value is left unset.
+    pub fn parse_from(args: impl IntoIterator<Item = String>) -> Self {
+        let mut parsed = Args::default();
+        let mut positional: Vec<String> = Vec::new();
+        let (mut origin, mut diff) = (false, false);
+        let mut args = args.into_iter().peekable();
+        while let Some(arg) = args.next() {
+            let (name, value) = match arg.split_once('=') {
+                Some((name, value)) => (name.to_string(), Some(value.to_string())),
+                None => (arg, None),
+            };
+            match name.as_str() {
+                "--config" => {
+                    parsed.config = value
+                        .or_else(|| args.next_if(|arg| !arg.starts_with('-')))
+                        .map(PathBuf::from)
+                }
+                "--profile" => {
+                    parsed.profile = value.or_else(|| args.next_if(|arg| !arg.starts_with('-')))
+                }
+                "--origin" => origin = true,
+                "--diff" => diff = true,
+                _ if !name.starts_with('-') && value.is_none() => positional.push(name),
+                _ => {}
+            }
+        }
+        parsed.command = match positional.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+            ["config", "env"] => Some(Command::ConfigEnv),
+            ["config", "show"] => Some(Command::ConfigShow { origin, diff }),
+            _ => None,
+        };
+        parsed
+    }
+}
+
+/// Run a command, printing its output to the console.
+pub fn run(command: Command, layers: &ConfigLayers) {
+    attach_console();
+    match command {
+        Command::ConfigEnv => print_env_vars(),
+        Command::ConfigShow { origin, diff } => print_config(layers, origin, diff),
+    }
+}
+
+/// Attach to the console of the calling process.
+///
+/// Release builds use the Windows GUI subsystem, so they start without a console,
+/// and anything printed would be lost otherwise.
+#[cfg(windows)]
+fn attach_console() {
+    use winapi::um::wincon::{AttachConsole, ATTACH_PARENT_PROCESS};
+    // SAFETY: AttachConsole has no preconditions; it fails harmlessly if the
+    // process already has a console or the parent has none.
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(windows))]
+fn attach_console() {}
+
+/// Print the effective settings, as `key = value` lines with JSON values.
+fn print_config(layers: &ConfigLayers, origin: bool, diff: bool) {
+    let origins = match init::config_origins(layers) {
+        Ok(origins) => origins,
+        Err(e) => {
+            eprintln!("Unable to load the configuration: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    for setting in origins.iter().filter(|o| !diff || !o.is_default()) {
+        let mut line = format!("{} = {}", setting.key, setting.value);
+        if diff {
+            let default = setting.default.as_deref().unwrap_or("none");
+            line.push_str(&format!(" (default: {})", default));
+        }
+        if origin {
+            line.push_str(&format!("  # {}", setting.source));
+            if let Some(profile) = &setting.profile {
+                line.push_str(&format!(", profile `{}`", profile));
+            }
+        }
+        println!("{}", line);
+    }
+}
+
+/// Print the environment variables overriding settings.
+fn print_env_vars() {
+    let vars = match Config::env_vars() {
+        Ok(vars) => vars,
+        Err(e) => {
+            eprintln!("Unable to list environment variables: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    println!(
+        "Settings can be overridden with `{}` environment variables, separating nested keys with `{}`.",
+        ENV_PREFIX, ENV_SEPARATOR
+    );
+    println!(
+        "`{}PROFILE` selects the configuration profile.\n",
+        ENV_PREFIX
+    );
+    for var in vars {
+        println!("{} ({})", var.name, var.kind);
+        if !var.description.is_empty() {
+            println!("    {}", var.description.replace('\n', "\n    "));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Args {
+        Args::parse_from(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(parse(&[]), Args::default());
+
+        // Both `--name value` and `--name=value` forms.
+        let args = parse(&["--config", "/tmp/memospot.yaml", "--profile=work"]);
+        assert_eq!(args.config, Some(PathBuf::from("/tmp/memospot.yaml")));
+        assert_eq!(args.profile, Some("work".to_string()));
+        assert_eq!(args.command, None);
+        assert_eq!(
+            parse(&["--config=a=b.yaml"]).config,
+            Some(PathBuf::from("a=b.yaml"))
+        );
+
+        // Missing values.
+        assert_eq!(parse(&["--profile"]).profile, None);
+        let args = parse(&["--config", "--profile", "work"]);
+        assert_eq!(args.config, None);
+        assert_eq!(args.profile, Some("work".to_string()));
+
+        // Unknown options, such as those passed by the OS, are ignored.
+        let args = parse(&[
+            "-psn_0_12345",
+            "--verbose",
+            "--unknown=value",
+            "--profile",
+            "work",
+        ]);
+        assert_eq!(args.profile, Some("work".to_string()));
+        assert_eq!(args.command, None);
+
+        // Commands.
+        assert_eq!(parse(&["config", "env"]).command, Some(Command::ConfigEnv));
+        assert_eq!(
+            parse(&["--diff", "config", "show", "--origin"]).command,
+            Some(Command::ConfigShow {
+                origin: true,
+                diff: true
+            })
+        );
+        assert_eq!(parse(&["config"]).command, None);
+        assert_eq!(parse(&["config", "show", "extra"]).command, None);
+    }
+}
@@ -1,12 +1,19 @@
+use crate::cli::Args;
+use crate::logging;
+use crate::remote;
 use crate::runtime_config::RuntimeConfig;
+use crate::secrets;
 use crate::sqlite;
 /// Runtime checks and initialization code.
 ///
 /// Functions in this module panics with native dialogs instead of returning errors.
 /// Main purpose is to unclutter `main.rs`.
+///
+/// `try_` variants return errors instead, for use while the app is running.
 use crate::webview;
 use crate::zip;
-use config::Config;
+use anyhow::{anyhow, bail, Context};
+use config::{Config, ConfigLayers, Origin, Proxy};
 use homedir::HomeDirExt;
 use log::{debug, info, warn};
 use memospot::*;
@@ -14,8 +21,6 @@ use migration::{Migrator, MigratorTrait};
 use native_dialog::MessageType;
 use std::env;
 use std::env::consts::OS;
-use std::fs::File;
-use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::process::exit;
 use tokio::time::Instant;
@@ -43,11 +48,11 @@ pub fn data_path(app_name: &str) -> PathBuf {
     data_path
 }
 
-/// Ensure that Memos data directory exists and is writable.
+/// Resolve Memos data directory, without checking whether it exists.
 ///
 /// Use Memospot data directory if user-provided path is empty or ".".
 /// Optionally, resolve a user-provided data directory.
-pub fn memos_data(rtcfg: &RuntimeConfig) -> PathBuf {
+pub fn resolve_memos_data(rtcfg: &RuntimeConfig) -> PathBuf {
     let data_str = rtcfg
         .yaml
         .memos
@@ -64,13 +69,26 @@ pub fn memos_data(rtcfg: &RuntimeConfig) -> PathBuf {
     }
 
     let expanded_path = PathBuf::from(data_str).expand_home().unwrap_or_default();
-    let path = absolute_path(expanded_path)
-        .unwrap_or_else(|_| rtcfg.paths.memospot_data.to_path_buf());
+    absolute_path(expanded_path).unwrap_or_else(|_| rtcfg.paths.memospot_data.to_path_buf())
+}
+
+/// Ensure that Memos data directory exists and is writable.
+///
+/// See [`resolve_memos_data`].
+pub fn memos_data(rtcfg: &RuntimeConfig) -> PathBuf {
+    try_memos_data(rtcfg).unwrap_or_else(|e| {
+        panic_dialog!("{:#}", e);
+    })
+}
+
+/// Non-fatal version of [`memos_data`].
+pub fn try_memos_data(rtcfg: &RuntimeConfig) -> anyhow::Result<PathBuf> {
+    let path = resolve_memos_data(rtcfg);
     if path.exists() && path.is_dir() {
-        return path;
+        return Ok(path);
     }
 
-    panic_dialog!(
+    bail!(
         "Failed to resolve custom Memos data directory!\n{}\n\nEnsure it exists and is a directory, or remove the setting `memos.data` to use the default data path.",
         path.to_string_lossy()
     );
@@ -81,6 +99,13 @@ pub fn memos_data(rtcfg: &RuntimeConfig) -> PathBuf {
 /// Use Memospot data directory if user-provided path is empty or ".".
 /// Optionally, resolve a user-provided directory.
 pub fn backup_directory(rtcfg: &RuntimeConfig) -> PathBuf {
+    try_backup_directory(rtcfg).unwrap_or_else(|e| {
+        panic_dialog!("{:#}", e);
+    })
+}
+
+/// Non-fatal version of [`backup_directory`].
+pub fn try_backup_directory(rtcfg: &RuntimeConfig) -> anyhow::Result<PathBuf> {
     let folder_name = "backups";
     let default_path = rtcfg.paths.memospot_data.join(folder_name);
 
@@ -104,31 +129,37 @@ pub fn backup_directory(rtcfg: &RuntimeConfig) -> PathBuf {
     };
 
     if !path.exists() {
-        if let Err(e) = std::fs::create_dir_all(&path) {
-            panic_dialog!(
-                "Failed to create backup directory `{}`:\n{}",
-                path.to_string_lossy(),
-                e.to_string()
-            );
-        }
+        std::fs::create_dir_all(&path).with_context(|| {
+            format!(
+                "Failed to create backup directory `{}`",
+                path.to_string_lossy()
+            )
+        })?;
     }
 
     if path.is_file() {
-        panic_dialog!("Backup directory is a file:\n{}", path.to_string_lossy());
+        bail!("Backup directory is a file:\n{}", path.to_string_lossy());
     }
 
     if !&path.is_writable() {
-        panic_dialog!(
+        bail!(
             "Backup directory is not writable:\n{}",
             path.to_string_lossy()
         );
     }
 
-    path
+    Ok(path)
 }
 
 /// Ensure that database files are writable, if they exist.
 pub fn database(rtcfg: &RuntimeConfig) -> PathBuf {
+    try_database(rtcfg).unwrap_or_else(|e| {
+        panic_dialog!("{:#}", e);
+    })
+}
+
+/// Non-fatal version of [`database`].
+pub fn try_database(rtcfg: &RuntimeConfig) -> anyhow::Result<PathBuf> {
     let db_file = &format!(
         "memos_{}.db",
         rtcfg.yaml.memos.mode.as_deref().unwrap_or_default()
@@ -151,35 +182,45 @@ pub fn database(rtcfg: &RuntimeConfig) -> PathBuf {
             continue;
         }
         if file.exists() && !&file.is_writable() {
-            panic_dialog!("Database file is not writable:\n{}", file.to_string_lossy());
+            bail!("Database file is not writable:\n{}", file.to_string_lossy());
         }
     }
-    db_path
+    Ok(db_path)
 }
 
 /// Run database migrations.
 pub async fn migrate_database(rtcfg: &RuntimeConfig) {
+    if let Err(e) = try_migrate_database(rtcfg).await {
+        panic_dialog!("{:#}", e);
+    }
+}
+
+/// Non-fatal version of [`migrate_database`].
+///
+/// Failed backups and migrations are still reported with a warning dialog.
+pub async fn try_migrate_database(rtcfg: &RuntimeConfig) -> anyhow::Result<()> {
     if !rtcfg.yaml.memospot.migrations.enabled.unwrap_or_default() {
         warn!("Database migrations were disabled via configuration.");
-        return;
+        return Ok(());
     }
     if !rtcfg.paths.memos_db_file.exists() {
-        return;
+        return Ok(());
     }
 
     let db = sqlite::get_database_connection(rtcfg)
         .await
-        .unwrap_or_else(|e| {
-            panic_dialog!("Failed to connect to the database:\n{}", e.to_string());
-        });
-    let migration_amount = Migrator::get_pending_migrations(&db)
+        .context("Failed to connect to the database")?;
+    let pending_migrations = Migrator::get_pending_migrations(&db)
         .await
-        .unwrap_or_default()
-        .len();
+        .unwrap_or_default();
     let _ = db.close().await;
+    let migration_amount = pending_migrations.len();
     if migration_amount == 0 {
         debug!("No pending migrations found.");
-        return;
+        return Ok(());
+    }
+    for migration in &pending_migrations {
+        info!(migration = migration.name(); "Pending migration: {}", migration.name());
     }
 
     if rtcfg.yaml.memospot.backups.enabled.unwrap_or_default() {
@@ -195,6 +236,8 @@ pub async fn migrate_database(rtcfg: &RuntimeConfig) {
         match backup.await {
             Ok(_) => {
                 info!(
+                    backup_path:% = backup_path.display(),
+                    elapsed_ms = start_time.elapsed().as_millis() as u64;
                     "Database backup completed successfully! Operation took {:?}. Backup file: {}",
                     start_time.elapsed(),
                     backup_path.to_string_lossy()
@@ -209,25 +252,26 @@ pub async fn migrate_database(rtcfg: &RuntimeConfig) {
     let start_time = Instant::now();
     let db = sqlite::get_database_connection(rtcfg)
         .await
-        .unwrap_or_else(|e| {
-            panic_dialog!("Failed to connect to the database:\n{}", e.to_string());
-        });
+        .context("Failed to connect to the database")?;
     if let Err(e) = Migrator::up(&db, None).await {
         warn_dialog!("Failed to run database migrations:\n{}", e.to_string());
     }
-    db.close().await.unwrap_or_else(|e| {
-        panic_dialog!("Failed to close database connection:\n{}", e.to_string());
-    });
+    db.close()
+        .await
+        .context("Failed to close database connection")?;
 
     info!(
+        migrations = migration_amount,
+        elapsed_ms = start_time.elapsed().as_millis() as u64;
         "Database migrations took {:?}. Ran {} migrations.",
         start_time.elapsed(),
         migration_amount,
     );
+    Ok(())
 }
 
 /// Ensure that WebView is available.
-pub fn ensure_webview() {
+pub fn ensure_webview(proxy: &Proxy) {
     if webview::is_available() {
         return;
     }
@@ -244,7 +288,7 @@ pub fn ensure_webview() {
     }
 
     tauri::async_runtime::block_on(async move {
-        if let Err(e) = webview::install().await {
+        if let Err(e) = webview::install(proxy).await {
             error_dialog!(
                 "Failed to install WebView:\n{}\n\nPlease install it manually.",
                 e
@@ -265,13 +309,68 @@ pub fn ensure_webview() {
     }
 }
 
+/// Offer to restore the previous configuration file version from its backup.
+///
+/// Returns true if the backup was restored.
+fn restore_config_backup(config_path: &Path) -> bool {
+    let backup = Config::backup_path(config_path);
+    if !backup.is_file() || !Config::validate_file(&backup).is_empty() {
+        return false;
+    }
+
+    let user_confirmed = confirm_dialog(
+        "Configuration Error",
+        &format!(
+            "Invalid configuration file:\n{}\n\n\
+            A backup of its previous version is available. Do you want to restore it?",
+            config_path.to_string_lossy()
+        ),
+        MessageType::Warning,
+    );
+    if !user_confirmed {
+        return false;
+    }
+
+    match Config::restore_backup(config_path) {
+        Ok(()) => {
+            info!(
+                "Configuration restored from `{}`.",
+                backup.to_string_lossy()
+            );
+            true
+        }
+        Err(e) => {
+            warn!("Failed to restore configuration backup: {}", e);
+            false
+        }
+    }
+}
+
+/// Locate the user configuration file.
+///
+/// Uses the `--config` argument if given, or `memospot.yaml` in the data directory.
+pub fn config_path(args: &Args, memospot_data: &Path) -> PathBuf {
+    let Some(path) = &args.config else {
+        return memospot_data.join("memospot.yaml");
+    };
+    let expanded_path = path.expand_home().unwrap_or_else(|_| path.clone());
+    absolute_path(&expanded_path).unwrap_or(expanded_path)
+}
+
 /// Initialize application configuration.
 ///
 /// - Ensure that configuration file exists and is writable.
-/// - If configuration file is missing or malformed, optionally reset it to defaults.
-pub fn config(config_path: &PathBuf) -> Config {
+/// - If configuration file is missing, create it with defaults.
+/// - If configuration file is malformed, optionally restore its backup, or repair it,
+///   keeping valid settings.
+///   Reset it to defaults if it can't be repaired.
+/// - Report invalid environment variables, system or policy files as such,
+///   leaving the user file alone.
+/// - Load the configuration from every layer.
+pub fn config(layers: &ConfigLayers) -> Config {
+    let config_path = &layers.user;
     if !config_path.exists() {
-        if let Err(e) = Config::reset_file(config_path) {
+        if let Err(e) = layers.reset() {
             panic_dialog!(
                 "Failed to create configuration file:\n{}\n\n{}",
                 config_path.to_string_lossy(),
@@ -294,14 +393,42 @@ pub fn config(config_path: &PathBuf) -> Config {
         );
     }
 
-    let mut cfg_reader = Config::init(config_path);
-    if let Err(e) = cfg_reader {
+    let mut cfg_reader = layers.extract();
+    let mut errors = layers.validate();
+
+    // Errors outside the user file can't be fixed by restoring or repairing it.
+    let outside: Vec<String> = errors
+        .iter()
+        .filter(|e| !e.in_user_file())
+        .map(|e| format!("- {}", e))
+        .collect();
+    if !outside.is_empty() {
+        panic_dialog!(
+            "Invalid settings outside the configuration file:\n\n{}\n\nPlease fix them and restart the application.",
+            outside.join("\n")
+        );
+    }
+
+    if (cfg_reader.is_err() || !errors.is_empty()) && restore_config_backup(config_path) {
+        cfg_reader = layers.extract();
+        errors = layers.validate();
+    }
+    if cfg_reader.is_err() || !errors.is_empty() {
+        let details = match &cfg_reader {
+            Err(e) if errors.is_empty() => e.to_string(),
+            _ => errors
+                .iter()
+                .map(|e| format!("- {}", e))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        };
         let user_confirmed = confirm_dialog(
             "Configuration Error",
             &format!(
-                "Failed to parse configuration file:\n\n{}\n\n\
-                Do you want to reset the configuration file and start the application with default settings?",
-                e
+                "Invalid configuration file:\n{}\n\n{}\n\n\
+                Do you want to repair the configuration file? Invalid settings will be reset to defaults.",
+                config_path.to_string_lossy(),
+                details
             ),
             MessageType::Warning
         );
@@ -310,20 +437,72 @@ pub fn config(config_path: &PathBuf) -> Config {
             panic_dialog!("You must fix the config file manually and restart the application.");
         }
 
-        if let Err(e) = Config::reset_file(config_path) {
-            panic_dialog!(
-                "Failed to reset configuration file `{}`:\n{}",
-                config_path.to_string_lossy(),
-                e.to_string()
-            );
+        match layers.recover() {
+            Ok(recovery) => {
+                let dropped = if recovery.dropped.is_empty() {
+                    "- none".to_string()
+                } else {
+                    recovery
+                        .dropped
+                        .iter()
+                        .map(|e| format!("- {}", e))
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                };
+                info_dialog!(
+                    "Configuration file repaired.\n\nDropped settings:\n{}\n\nThe original file was saved to:\n{}",
+                    dropped,
+                    recovery.backup.to_string_lossy()
+                );
+                cfg_reader = layers.extract();
+            }
+            Err(e) => {
+                let user_confirmed = confirm_dialog(
+                    "Configuration Error",
+                    &format!(
+                        "Unable to repair configuration file:\n{}\n\n\
+                        Do you want to reset the configuration file and start the application with default settings?",
+                        e
+                    ),
+                    MessageType::Warning
+                );
+
+                if !user_confirmed {
+                    panic_dialog!(
+                        "You must fix the config file manually and restart the application."
+                    );
+                }
+
+                if let Err(e) = layers.reset() {
+                    panic_dialog!(
+                        "Failed to reset configuration file `{}`:\n{}",
+                        config_path.to_string_lossy(),
+                        e.to_string()
+                    );
+                }
+                cfg_reader = layers.extract();
+            }
         }
-        cfg_reader = Ok(Config::default());
     }
 
     let mut config = cfg_reader.unwrap_or_else(|e| {
         panic_dialog!("Failed to parse configuration file:\n{}", e.to_string());
     });
+    // Files from older releases are only rewritten once they're known to be valid.
+    match layers.upgrade() {
+        Ok(true) => info!(
+            "Configuration file upgraded to version {}.",
+            Config::VERSION
+        ),
+        Ok(false) => {}
+        Err(e) => warn!("Failed to upgrade configuration file: {}", e),
+    }
+    debug_overrides(&mut config);
+    config
+}
 
+/// Settings overridden in debug builds, whatever the configuration says.
+pub fn debug_overrides(config: &mut Config) {
     if cfg!(debug_assertions) {
         // Use Memos in demo mode during development,
         // as it's already seeded with some data.
@@ -335,7 +514,16 @@ pub fn config(config_path: &PathBuf) -> Config {
             config.memos.port = Some(current_port + 1);
         }
     }
-    config
+}
+
+/// List every setting loaded from the configuration layers, with where its value comes from.
+///
+/// Includes the settings overridden in debug builds.
+pub fn config_origins(layers: &ConfigLayers) -> std::io::Result<Vec<Origin>> {
+    let origins = layers.origins()?;
+    let mut config = layers.extract()?;
+    debug_overrides(&mut config);
+    Ok(config.override_origins(origins, "debug build"))
 }
 
 /// Ensure that Memos port is available.
@@ -343,31 +531,30 @@ pub fn config(config_path: &PathBuf) -> Config {
 /// Tries to find a free port if the configured one is already
 /// in use and updates the referenced configuration in place.
 pub fn memos_port(rtcfg: &RuntimeConfig) -> u16 {
-    if let Some(free_port) =
-        portpicker::find_free_port(rtcfg.yaml.memos.port.unwrap_or_default())
-    {
-        return free_port;
-    }
+    try_memos_port(rtcfg).unwrap_or_else(|e| {
+        panic_dialog!("{:#}", e);
+    })
+}
 
-    panic_dialog!("Failed to find an open port!");
+/// Non-fatal version of [`memos_port`].
+pub fn try_memos_port(rtcfg: &RuntimeConfig) -> anyhow::Result<u16> {
+    portpicker::find_free_port(rtcfg.yaml.memos.port.unwrap_or_default())
+        .ok_or_else(|| anyhow!("Failed to find an open port!"))
 }
 
 /// Memos URL.
 ///
-/// If remote server is enabled, return the configured URL.
+/// If remote server is enabled, return the URL of the active remote profile.
 /// Otherwise, return the default Memos address for the spawned server.
 pub fn memos_url(rtcfg: &RuntimeConfig) -> String {
-    if !rtcfg.yaml.memospot.remote.enabled.unwrap_or_default()
-        || rtcfg.yaml.memospot.remote.url.is_none()
-    {
+    let Some(url) = rtcfg.yaml.memospot.remote.active_url() else {
         return format!(
             "http://localhost:{}/",
             rtcfg.yaml.memos.port.unwrap_or_default()
         );
-    }
+    };
 
-    let url = rtcfg.yaml.memospot.remote.url.as_deref().unwrap();
-    if url.is_empty() || !url.starts_with("http") {
+    if !is_valid_remote_url(url) {
         panic_dialog!(
             "Invalid remote server URL: `{}`\n\nURL must start with http:// or https://.\nCheck memospot.yaml.",
             url
@@ -377,6 +564,139 @@ pub fn memos_url(rtcfg: &RuntimeConfig) -> String {
     url.trim_end_matches('/').to_string() + "/"
 }
 
+/// Ensure that the remote server is reachable.
+///
+/// Applies the `memospot.remote.fallback` policy if it's not:
+/// - `error`: show an error message and carry on.
+/// - `retry`: ask whether to check again, until the user gives up.
+/// - `local`: fall back to the local Memos server.
+pub fn remote_server(rtcfg: &mut RuntimeConfig) {
+    if rtcfg.managed_server {
+        return;
+    }
+
+    let policy = rtcfg
+        .yaml
+        .memospot
+        .remote
+        .fallback
+        .clone()
+        .unwrap_or_default();
+    let tls = rtcfg.yaml.memospot.remote.active_tls().clone();
+    loop {
+        let Err(e) = tauri::async_runtime::block_on(remote::check(
+            &rtcfg.memos_url,
+            &tls,
+            &rtcfg.yaml.memospot.network.proxy,
+        )) else {
+            info!("Remote server is reachable: {}", rtcfg.memos_url);
+            if remote::has_custom_tls(&tls) {
+                warn!("Custom TLS settings only apply to Memospot's own requests. The server certificate must also be trusted by the operating system for the window to load Memos.");
+            }
+            return;
+        };
+        warn!("Remote server `{}` is unreachable: {}", rtcfg.memos_url, e);
+
+        match policy.as_str() {
+            "retry" => {
+                let user_confirmed = confirm_dialog(
+                    "Remote Server Unreachable",
+                    &format!(
+                        "Unable to reach the remote server:\n{}\n\n{}\n\nDo you want to try again?",
+                        rtcfg.memos_url, e
+                    ),
+                    MessageType::Warning,
+                );
+                if user_confirmed {
+                    continue;
+                }
+                warn!("User declined to check the remote server again.");
+                return;
+            }
+            "local" => {
+                rtcfg.managed_server = true;
+                rtcfg.offline_fallback = true;
+                rtcfg.memos_url = format!(
+                    "http://localhost:{}/",
+                    rtcfg.yaml.memos.port.unwrap_or_default()
+                );
+                warn!(
+                    "Falling back to the local Memos server at {}.",
+                    rtcfg.memos_url
+                );
+                return;
+            }
+            _ => {
+                error_dialog!(
+                    "Unable to reach the remote server:\n{}\n\n{}\n\nCheck your settings and ensure that Memos is reachable.",
+                    rtcfg.memos_url,
+                    e
+                );
+                return;
+            }
+        }
+    }
+}
+
+/// Move plain-text remote access tokens to the system keyring.
+///
+/// The configuration file is saved right away, so tokens don't linger on disk.
+pub fn access_tokens(rtcfg: &mut RuntimeConfig) {
+    match remote::store_access_tokens(&mut rtcfg.yaml.memospot.remote) {
+        Ok(false) => {}
+        Ok(true) => {
+            info!("Access tokens moved to the system keyring.");
+            rtcfg
+                .__yaml__
+                .memospot
+                .remote
+                .clone_from(&rtcfg.yaml.memospot.remote);
+            if let Err(e) = rtcfg.paths.memospot_config_layers.save(&rtcfg.__yaml__) {
+                warn_dialog!(
+                    "Failed to save configuration file:\n{}\n\n{}",
+                    rtcfg.paths.memospot_config_file.to_string_lossy(),
+                    e
+                );
+            }
+        }
+        Err(e) => {
+            warn_dialog!(
+                "Failed to store access tokens in the system keyring:\n{}",
+                e
+            );
+        }
+    }
+}
+
+/// Check that secret references in the configuration can be resolved.
+///
+/// Unresolved secrets are reported, and the related settings are left empty.
+pub fn secrets(rtcfg: &RuntimeConfig) {
+    let failures = secrets::check(&rtcfg.yaml);
+    if !failures.is_empty() {
+        warn_dialog!(
+            "Some secrets referenced by the configuration could not be read:\n\n{}",
+            failures.join("\n")
+        );
+    }
+}
+
+/// Warn about `MEMOSPOT_*` environment variables that don't match any setting.
+pub fn env_overrides() {
+    let names = env::vars_os().filter_map(|(name, _)| name.into_string().ok());
+    for name in Config::unknown_env_vars(names) {
+        warn!(
+            "Ignoring unknown environment variable `{}`. Run `memospot config env` to list the supported variables.",
+            name
+        );
+    }
+}
+
+/// Check whether a remote server URL is usable.
+pub fn is_valid_remote_url(url: &str) -> bool {
+    !url.is_empty() && url.starts_with("http")
+}
+
 /// Locate Memos server binary.
 ///
 /// Look for Memos server binary in the following order:
@@ -432,91 +752,33 @@ pub fn find_memos(rtcfg: &RuntimeConfig) -> PathBuf {
     panic_dialog!("Unable to find Memos server!");
 }
 
-static LOGGING_CONFIG_YAML: &str = r#"
-# Log4rs configuration file.
-# https://github.com/estk/log4rs#quick-start
-#
-# Use absolute paths for file appender. Otherwise, it'll try to write next to the application binary.
-# Data directory is available as: $ENV{MEMOSPOT_DATA}
-appenders:
-    file:
-        encoder:
-            pattern: "{d(%Y-%m-%d %H:%M:%S)} - {h({l})}: {m}{n}"
-        path: $ENV{MEMOSPOT_DATA}/memospot.log
-        kind: rolling_file
-        policy:
-            trigger:
-                kind: size
-                limit: 10 mb
-            roller:
-                kind: fixed_window
-                pattern: $ENV{MEMOSPOT_DATA}/memospot.log.{}.gz
-                count: 5
-                base: 1
-root:
-    # debug | info | warn | error | off
-    level: info
-    appenders:
-        - file
-"#;
-
 /// Setup logging if it's enabled.
 ///
-/// - Validates `logging_config.yaml`.
+/// - Retires the legacy `logging_config.yaml` file, warning if it was customized.
 ///
 /// Return true if logging is enabled.
 pub fn setup_logger(rtcfg: &RuntimeConfig) -> bool {
-    if !rtcfg.yaml.memospot.log.enabled.unwrap_or_default() {
-        return false;
-    }
-    let log_config: PathBuf = rtcfg.paths.memospot_data.join("logging_config.yaml");
-
-    // SAFETY: We're setting an environment variable, which is generally safe.
-    // The unsafe block is required due to the potential for race conditions in
-    // a multithreaded context.
-    unsafe {
-        // Allows using $ENV{MEMOSPOT_DATA} in log4rs config.
-        std::env::set_var(
-            "MEMOSPOT_DATA",
-            rtcfg.paths.memospot_data.to_string_lossy().to_string(),
-        );
-    }
-    if log4rs::init_file(&log_config, Default::default()).is_ok() {
-        // logging is enabled and config is ok
-        return true;
-    }
+    let enabled = match logging::apply(rtcfg) {
+        Ok(enabled) => enabled,
+        Err(e) => {
+            warn_dialog!("Failed to setup logging:\n{:#}", e);
+            false
+        }
+    };
 
-    // Logging is enabled, but config is bad.
-    if let Ok(mut file) = File::create(&log_config) {
-        let config_template = LOGGING_CONFIG_YAML.replace("    ", "  ");
-        if let Err(e) = file.write_all(config_template.as_bytes()) {
-            panic_dialog!(
-                "Failed to write to `{}`:\n{}",
-                log_config.to_string_lossy(),
-                e.to_string()
+    match logging::retire_legacy_config(&rtcfg.paths.memospot_data) {
+        Ok(Some(renamed)) => {
+            warn!(
+                "Legacy logging configuration renamed to `{}`.",
+                renamed.to_string_lossy()
             );
-        }
-        if let Err(e) = file.flush() {
-            panic_dialog!(
-                "Failed to flush `{}` to disk:\n{}",
-                log_config.to_string_lossy(),
-                &e
+            warn_dialog!(
+                "Logging is now configured by the `memospot.log` settings in memospot.yaml, and `logging_config.yaml` is no longer used.\n\nYour customized file was renamed to `{}`. Please move its settings to memospot.yaml.",
+                renamed.to_string_lossy()
             );
         }
-    } else {
-        panic_dialog!(
-            "Failed to truncate `{}`. Please delete it and restart the application.",
-            log_config.to_string_lossy()
-        );
+        Ok(None) => {}
+        Err(e) => warn!("Failed to retire the legacy logging configuration: {:#}", e),
     }
-
-    if log4rs::init_file(&log_config, Default::default()).is_ok() {
-        // Logging is enabled and config was reset.
-        return true;
-    }
-
-    panic_dialog!(
-        "Failed to setup logging!\nPlease delete `{}` and restart the application.",
-        log_config.to_string_lossy()
-    );
+    enabled
 }
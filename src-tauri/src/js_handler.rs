@@ -5,7 +5,19 @@
 //!
 //! The TypeScript/JavaScript API is defined in `src-ui/src/tauri.ts`.
 
-use tauri::{command, State};
+use crate::logging::{self, LogEntry, LogFile};
+use crate::runtime_config::RuntimeConfig;
+use crate::{init, memos, relocate, remote, secrets, sqlite, support};
+use config::{Bundle, Origin};
+use log::{error, info};
+use memospot::warn_dialog;
+use migration::assets::{self, AssetReport};
+use native_dialog::FileDialog;
+use serde::Serialize;
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::utils::config::AppUrl;
+use tauri::{command, AppHandle, Manager, State, Window, WindowBuilder, WindowUrl};
 use tokio::sync::Mutex;
 
 pub struct MemosURL(pub Mutex<String>);
@@ -15,6 +27,38 @@ impl MemosURL {
     }
 }
 
+pub struct RuntimeConfigState(pub Mutex<RuntimeConfig>);
+impl RuntimeConfigState {
+    pub fn manage(rtcfg: RuntimeConfig) -> Self {
+        Self(Mutex::new(rtcfg))
+    }
+}
+
+/// Whether a window shows Memospot's own pages, rather than Memos.
+///
+/// Memos pages can call commands too, so commands writing files or secrets
+/// must only be available to Memospot's pages.
+fn is_app_window(window: &Window) -> bool {
+    let url = window.url();
+    if url.scheme() == "tauri" || url.host_str() == Some("tauri.localhost") {
+        return true;
+    }
+    if cfg!(debug_assertions) {
+        if let AppUrl::Url(WindowUrl::External(dev_url)) = &window.config().build.dev_path {
+            return url.origin() == dev_url.origin();
+        }
+    }
+    false
+}
+
+/// Reject commands called from windows that don't show Memospot's own pages.
+fn ensure_app_window(window: &Window) -> Result<(), String> {
+    if is_app_window(window) {
+        return Ok(());
+    }
+    Err("This command is only available to Memospot windows.".into())
+}
+
 #[command]
 pub async fn get_memos_url(memos_url: State<'_, MemosURL>) -> Result<String, String> {
     Ok(memos_url.0.lock().await.clone())
@@ -24,3 +68,446 @@ pub async fn get_memos_url(memos_url: State<'_, MemosURL>) -> Result<String, Str
 pub async fn get_env(name: &str) -> Result<String, String> {
     Ok(std::env::var(String::from(name)).unwrap_or(String::from("")))
 }
+
+/// Scan Memos assets for orphaned files and resources with missing files.
+///
+/// If `quarantine` is true, orphaned files are moved to `{memos_data}/quarantine`.
+/// Recently modified files are left in place, as Memos may still be recording them.
+#[command]
+pub async fn scan_assets(
+    quarantine: bool,
+    rtcfg: State<'_, RuntimeConfigState>,
+) -> Result<AssetReport, String> {
+    let rtcfg = rtcfg.0.lock().await.clone();
+    let data_dir = rtcfg.paths.memos_data.clone();
+
+    let db = sqlite::get_database_connection(&rtcfg)
+        .await
+        .map_err(|e| e.to_string())?;
+    let report = assets::scan(&db, &data_dir).await;
+    let _ = db.close().await;
+    let report = report.map_err(|e| e.to_string())?;
+
+    if quarantine && !report.orphaned.is_empty() {
+        assets::quarantine(&report, &data_dir, &data_dir.join("quarantine"))
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(report)
+}
+
+/// Relocate Memos data directory to `target` and restart the application.
+///
+/// Memos is stopped during the operation. On failure, it's started again
+/// using the current data directory.
+#[command]
+pub async fn relocate_memos_data(
+    target: String,
+    window: Window,
+    app_handle: AppHandle,
+    rtcfg: State<'_, RuntimeConfigState>,
+) -> Result<(), String> {
+    ensure_app_window(&window)?;
+    // The state isn't locked during the copy, so the window stays responsive.
+    let current = rtcfg.0.lock().await.clone();
+    if !current.managed_server {
+        return Err("Data relocation is only available for the local Memos server.".into());
+    }
+
+    tauri::api::process::kill_children();
+    match relocate::memos_data(&current, &target).await {
+        Ok(relocated) => {
+            *rtcfg.0.lock().await = relocated;
+            app_handle.restart();
+            Ok(())
+        }
+        Err(e) => {
+            error!("Failed to relocate Memos data: {}", e);
+            if let Err(err) = memos::spawn(&current) {
+                error!("Failed to restart Memos server: {}", err);
+            }
+            Err(e.to_string())
+        }
+    }
+}
+
+/// Switch to a saved workspace.
+///
+/// Prepares the target workspace data directory and database, then restarts
+/// Memos with it. If the new server doesn't start, the previous workspace is
+/// started again.
+#[command]
+pub async fn switch_workspace(
+    name: String,
+    app_handle: AppHandle,
+    rtcfg: State<'_, RuntimeConfigState>,
+    memos_url: State<'_, MemosURL>,
+) -> Result<(), String> {
+    // The state isn't locked while the server restarts, so the window stays responsive.
+    let current = rtcfg.0.lock().await.clone();
+    if !current.managed_server {
+        return Err("Workspaces are only available for the local Memos server.".into());
+    }
+
+    let mut target = current.clone();
+    target
+        .yaml
+        .switch_workspace(&name)
+        .map_err(|e| e.to_string())?;
+    if let Err(e) = prepare_memos(&mut target) {
+        return Err(format!("Unable to prepare workspace `{}`:\n{:#}", name, e));
+    }
+
+    info!("Switching to workspace `{}`.", name);
+    tauri::api::process::kill_children();
+    sqlite::checkpoint(&current).await;
+
+    if let Err(e) = start_memos(&target).await {
+        error!("Failed to switch to workspace `{}`: {:#}", name, e);
+        tauri::api::process::kill_children();
+        if let Err(err) = memos::spawn(&current) {
+            error!("Failed to restart Memos server: {}", err);
+        }
+        return Err(format!(
+            "Unable to switch to workspace `{}`:\n{:#}",
+            name, e
+        ));
+    }
+
+    *memos_url.0.lock().await = target.memos_url.clone();
+    *rtcfg.0.lock().await = target.clone();
+
+    reload_main_window(&app_handle, &target).await
+}
+
+/// Pick a port and check the data directory, database and backup directory
+/// of a local Memos server, while the current one keeps running.
+fn prepare_memos(rtcfg: &mut RuntimeConfig) -> anyhow::Result<()> {
+    rtcfg.yaml.memos.port = Some(init::try_memos_port(rtcfg)?);
+    rtcfg.paths.memos_data = init::try_memos_data(rtcfg)?;
+    rtcfg.paths.memos_db_file = init::try_database(rtcfg)?;
+    rtcfg.paths._memospot_backups = init::try_backup_directory(rtcfg)?;
+    rtcfg.memos_url = init::memos_url(rtcfg);
+    Ok(())
+}
+
+/// Migrate the database and start the local Memos server, waiting until it's ready.
+async fn start_memos(rtcfg: &RuntimeConfig) -> anyhow::Result<()> {
+    init::try_migrate_database(rtcfg).await?;
+    memos::spawn(rtcfg)?;
+    if !memos::wait_until_ready(rtcfg, Duration::from_secs(30)).await {
+        anyhow::bail!("Memos server did not respond within a reasonable time.");
+    }
+    Ok(())
+}
+
+/// Update the main window title and navigate to the current Memos URL.
+///
+/// Waits for a local server to become ready before navigating.
+async fn reload_main_window(
+    app_handle: &AppHandle,
+    rtcfg: &RuntimeConfig,
+) -> Result<(), String> {
+    let Some(main_window) = app_handle.get_window("main") else {
+        return Ok(());
+    };
+
+    let version = app_handle.package_info().version.to_string();
+    main_window
+        .set_title(&rtcfg.window_title(&version))
+        .unwrap_or_default();
+
+    if rtcfg.managed_server && !memos::wait_until_ready(rtcfg, Duration::from_secs(30)).await {
+        return Err("Memos server did not respond within a reasonable time.".into());
+    }
+    let url = serde_json::to_string(&rtcfg.memos_url).map_err(|e| e.to_string())?;
+    main_window
+        .eval(&format!("window.location.replace({});", url))
+        .map_err(|e| e.to_string())
+}
+
+/// Switch the active remote server profile.
+///
+/// Use the profile name "local" to switch back to the local Memos server,
+/// which is started on demand. Switching to a remote server stops it.
+///
+/// The previous profile stays active if the remote server is unreachable
+/// or the local server fails to start.
+#[command]
+pub async fn switch_remote_profile(
+    name: String,
+    app_handle: AppHandle,
+    rtcfg: State<'_, RuntimeConfigState>,
+    memos_url: State<'_, MemosURL>,
+) -> Result<(), String> {
+    // The state isn't locked while servers are checked and started, so the
+    // window stays responsive.
+    let current = rtcfg.0.lock().await.clone();
+
+    let mut target = current.clone();
+    target
+        .yaml
+        .memospot
+        .remote
+        .select(&name)
+        .map_err(|e| e.to_string())?;
+    if let Some(url) = target.yaml.memospot.remote.active_url() {
+        if !init::is_valid_remote_url(url) {
+            return Err(format!(
+                "Invalid remote server URL: `{}`. URL must start with http:// or https://.",
+                url
+            ));
+        }
+        remote::check(
+            url,
+            target.yaml.memospot.remote.active_tls(),
+            &target.yaml.memospot.network.proxy,
+        )
+        .await
+        .map_err(|e| format!("Unable to reach the remote server `{}`:\n{}", url, e))?;
+    }
+    target.managed_server = target.selects_managed_server();
+    target.offline_fallback = false;
+
+    if !current.managed_server && target.managed_server {
+        if let Err(e) = prepare_memos(&mut target) {
+            return Err(format!(
+                "Unable to prepare the local Memos server:\n{:#}",
+                e
+            ));
+        }
+        if let Err(e) = start_memos(&target).await {
+            error!("Failed to start the local Memos server: {:#}", e);
+            tauri::api::process::kill_children();
+            return Err(format!("Unable to start the local Memos server:\n{:#}", e));
+        }
+    } else {
+        target.memos_url = init::memos_url(&target);
+    }
+    if current.managed_server && !target.managed_server {
+        info!("Stopping local Memos server.");
+        tauri::api::process::kill_children();
+        sqlite::checkpoint(&current).await;
+    }
+    info!(
+        "Switched to remote server profile `{}`: {}",
+        name, target.memos_url
+    );
+
+    *memos_url.0.lock().await = target.memos_url.clone();
+    *rtcfg.0.lock().await = target.clone();
+
+    reload_main_window(&app_handle, &target).await
+}
+
+/// Save a secret in the system keyring.
+///
+/// Configuration values can then refer to it as `secret:NAME`.
+#[command]
+pub async fn set_secret(name: String, value: String, window: Window) -> Result<(), String> {
+    ensure_app_window(&window)?;
+    secrets::store(&name, &value).map_err(|e| format!("{:#}", e))?;
+    info!("Secret `{}` saved to the system keyring.", name.trim());
+    Ok(())
+}
+
+/// List every effective setting, with where its value comes from.
+///
+/// Settings changed while the app is running are reported as such.
+/// If `changed_only` is true, settings with their default value are left out.
+#[command]
+pub async fn get_config_origins(
+    changed_only: bool,
+    rtcfg: State<'_, RuntimeConfigState>,
+) -> Result<Vec<Origin>, String> {
+    let rtcfg = rtcfg.0.lock().await.clone();
+    let origins =
+        init::config_origins(&rtcfg.paths.memospot_config_layers).map_err(|e| e.to_string())?;
+    let mut origins = rtcfg.yaml.override_origins(origins, "runtime");
+    if changed_only {
+        origins.retain(|origin| !origin.is_default());
+    }
+    Ok(origins)
+}
+
+/// File name filter of settings bundles.
+const BUNDLE_EXTENSIONS: &[&str] = &["yaml", "yml"];
+
+/// Export the settings to a portable bundle, at a path chosen with a save dialog.
+///
+/// Paths under the home directory are written as `~/...`.
+/// Returns the bundle path, or None if the dialog was cancelled.
+#[command]
+pub async fn export_settings(
+    window: Window,
+    rtcfg: State<'_, RuntimeConfigState>,
+) -> Result<Option<String>, String> {
+    ensure_app_window(&window)?;
+    let rtcfg = rtcfg.0.lock().await.clone();
+    let bundle =
+        Bundle::export(&rtcfg.paths.memospot_config_layers.user).map_err(|e| e.to_string())?;
+
+    let path = tauri::async_runtime::spawn_blocking(|| {
+        FileDialog::new()
+            .set_filename("memospot-settings.yaml")
+            .add_filter("Memospot settings", BUNDLE_EXTENSIONS)
+            .show_save_single_file()
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| e.to_string())?;
+    let Some(path) = path else {
+        return Ok(None);
+    };
+
+    std::fs::write(&path, bundle).map_err(|e| e.to_string())?;
+    info!("Settings exported to `{}`.", path.to_string_lossy());
+    Ok(Some(path.to_string_lossy().to_string()))
+}
+
+/// Import the settings from a bundle chosen with an open dialog, and restart the application.
+///
+/// The current configuration file is backed up first. Paths that can't be used
+/// on this machine are reported before restarting. Nothing changes if the
+/// dialog is cancelled.
+#[command]
+pub async fn import_settings(
+    window: Window,
+    app_handle: AppHandle,
+    rtcfg: State<'_, RuntimeConfigState>,
+) -> Result<(), String> {
+    ensure_app_window(&window)?;
+    let path = tauri::async_runtime::spawn_blocking(|| {
+        FileDialog::new()
+            .add_filter("Memospot settings", BUNDLE_EXTENSIONS)
+            .show_open_single_file()
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| e.to_string())?;
+    let Some(path) = path else {
+        return Ok(());
+    };
+
+    let contents = std::fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    let bundle = Bundle::import(&contents).map_err(|e| e.to_string())?;
+
+    let mut rtcfg = rtcfg.0.lock().await;
+    let layers = rtcfg.paths.memospot_config_layers.clone();
+    bundle.save(&layers.user).map_err(|e| e.to_string())?;
+    info!("Settings imported from `{}`.", path.to_string_lossy());
+
+    // Keep the imported file as is when exiting.
+    let imported = layers.extract().map_err(|e| e.to_string())?;
+    rtcfg.yaml = imported.clone();
+    rtcfg.__yaml__ = imported;
+    drop(rtcfg);
+
+    let problems = bundle.check_paths();
+    if !problems.is_empty() {
+        warn_dialog!(
+            "Some imported paths can't be used on this machine:\n\n{}",
+            problems.join("\n")
+        );
+    }
+    app_handle.restart();
+    Ok(())
+}
+
+/// Log files, and where to find them.
+#[derive(Debug, Serialize)]
+pub struct LogFiles {
+    /// Whether logging is enabled.
+    pub enabled: bool,
+    /// Path of the current log file.
+    pub path: String,
+    /// Current log file, followed by the rotated ones, newest first.
+    pub files: Vec<LogFile>,
+}
+
+/// List the current and rotated log files.
+#[command]
+pub async fn list_log_files(rtcfg: State<'_, RuntimeConfigState>) -> Result<LogFiles, String> {
+    let rtcfg = rtcfg.0.lock().await.clone();
+    let log = &rtcfg.yaml.memospot.log;
+    let data = &rtcfg.paths.memospot_data;
+    Ok(LogFiles {
+        enabled: log.enabled.unwrap_or_default(),
+        path: logging::file_path(log, data).to_string_lossy().to_string(),
+        files: logging::files(log, data)
+            .into_iter()
+            .map(|(file, _)| file)
+            .collect(),
+    })
+}
+
+/// Read the last `lines` records of a log file.
+///
+/// `file` is a name from `list_log_files`, defaulting to the current log file.
+/// Records can be filtered by minimum `level` and by `target`, which also
+/// matches its modules.
+#[command]
+pub async fn tail_log(
+    file: Option<String>,
+    lines: usize,
+    level: Option<String>,
+    target: Option<String>,
+    rtcfg: State<'_, RuntimeConfigState>,
+) -> Result<Vec<LogEntry>, String> {
+    let rtcfg = rtcfg.0.lock().await.clone();
+    let files = logging::files(&rtcfg.yaml.memospot.log, &rtcfg.paths.memospot_data);
+    let path = match file {
+        Some(name) => files.into_iter().find(|(file, _)| file.name == name),
+        None => files.into_iter().find(|(file, _)| !file.rotated),
+    };
+    let Some((_, path)) = path else {
+        return Ok(Vec::new());
+    };
+
+    tauri::async_runtime::spawn_blocking(move || {
+        logging::tail(&path, lines, level.as_deref(), target.as_deref())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| format!("{:#}", e))
+}
+
+/// Open the log window, or focus it if it's already open.
+#[command]
+pub async fn open_log_window(app_handle: AppHandle) -> Result<(), String> {
+    if let Some(window) = app_handle.get_window("logs") {
+        return window.set_focus().map_err(|e| e.to_string());
+    }
+    WindowBuilder::new(&app_handle, "logs", WindowUrl::App("logs.html".into()))
+        .title("Memospot Logs")
+        .inner_size(960.0, 600.0)
+        .build()
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Create a diagnostic support bundle and return its path.
+///
+/// Without a `path`, the bundle is saved to the downloads directory,
+/// or to the Memospot data directory if there's none.
+#[command]
+pub async fn create_support_bundle(
+    path: Option<String>,
+    window: Window,
+    app_handle: AppHandle,
+    rtcfg: State<'_, RuntimeConfigState>,
+) -> Result<String, String> {
+    ensure_app_window(&window)?;
+    let rtcfg = rtcfg.0.lock().await.clone();
+    let path = match path.filter(|path| !path.trim().is_empty()) {
+        Some(path) => PathBuf::from(path),
+        None => tauri::api::path::download_dir()
+            .filter(|dir| dir.is_dir())
+            .unwrap_or_else(|| rtcfg.paths.memospot_data.clone())
+            .join(support::file_name()),
+    };
+    let version = app_handle.package_info().version.to_string();
+    support::create(&rtcfg, &version, &path)
+        .await
+        .map_err(|e| format!("{:#}", e))?;
+    Ok(path.to_string_lossy().to_string())
+}
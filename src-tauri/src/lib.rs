@@ -143,6 +143,13 @@ pub fn absolute_path(path: impl AsRef<Path>) -> Result<PathBuf> {
     Ok(absolute_path)
 }
 
+/// Strip the scheme and the trailing slash from an URL, for display purposes.
+pub fn display_url(url: &str) -> &str {
+    url.trim_start_matches("http://")
+        .trim_start_matches("https://")
+        .trim_end_matches('/')
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -250,4 +257,10 @@ mod tests {
             }));
         Ok(())
     }
+
+    #[test]
+    fn test_display_url() {
+        assert_eq!(display_url("http://localhost:5230/"), "localhost:5230");
+        assert_eq!(display_url("https://memos.example.com"), "memos.example.com");
+    }
 }
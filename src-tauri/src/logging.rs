@@ -0,0 +1,659 @@
+//! Logging, configured by the `memospot.log` settings.
+//!
+//! The log4rs configuration is built from the settings, so it can be applied
+//! again when they change while the app is running.
+//!
+//! Log files, including rotated ones, can also be read back for the log viewer.
+
+use crate::runtime_config::RuntimeConfig;
+use anyhow::{Context, Result};
+use config::Log;
+use flate2::read::GzDecoder;
+use homedir::HomeDirExt;
+use log::kv::{self, Key, VisitSource};
+use log::{Level, LevelFilter, Record};
+use log4rs::append::console::ConsoleAppender;
+use log4rs::append::rolling_file::policy::compound::roll::fixed_window::FixedWindowRoller;
+use log4rs::append::rolling_file::policy::compound::trigger::size::SizeTrigger;
+use log4rs::append::rolling_file::policy::compound::CompoundPolicy;
+use log4rs::append::rolling_file::RollingFileAppender;
+use log4rs::config::{Appender, Logger, Root};
+use log4rs::encode::pattern::PatternEncoder;
+use log4rs::encode::{self, Encode};
+use log4rs::Handle;
+use memospot::absolute_path;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use std::fs;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+/// Log line layout.
+const PATTERN: &str = "{d(%Y-%m-%d %H:%M:%S)} - {h({l})} [{t}]: {m}{n}";
+
+/// Timestamp layout of text log lines.
+const TEXT_TIMESTAMP: &str = "%Y-%m-%d %H:%M:%S";
+
+/// Timestamp layout of JSON log lines.
+const JSON_TIMESTAMP: &str = "%Y-%m-%dT%H:%M:%S%.3f%:z";
+
+/// Size of the first chunk read from the end of a log file by `tail`.
+const TAIL_CHUNK: u64 = 64 * 1024;
+
+/// Log4rs configuration file read before the `memospot.log` settings existed.
+const LEGACY_CONFIG_FILE: &str = "logging_config.yaml";
+
+/// Contents of the legacy configuration file, as Memospot created it.
+const LEGACY_CONFIG_DEFAULT: &str = r#"# Log4rs configuration file.
+# https://github.com/estk/log4rs#quick-start
+#
+# Use absolute paths for file appender. Otherwise, it'll try to write next to the application binary.
+# Data directory is available as: $ENV{MEMOSPOT_DATA}
+appenders:
+  file:
+    encoder:
+      pattern: "{d(%Y-%m-%d %H:%M:%S)} - {h({l})}: {m}{n}"
+    path: $ENV{MEMOSPOT_DATA}/memospot.log
+    kind: rolling_file
+    policy:
+      trigger:
+        kind: size
+        limit: 10 mb
+      roller:
+        kind: fixed_window
+        pattern: $ENV{MEMOSPOT_DATA}/memospot.log.{}.gz
+        count: 5
+        base: 1
+root:
+  # debug | info | warn | error | off
+  level: info
+  appenders:
+    - file"#;
+
+/// Handle to the logger, set once it's initialized.
+static HANDLE: OnceLock<Handle> = OnceLock::new();
+
+/// Parse a log level, falling back to `info`.
+fn level(level: &Option<String>) -> LevelFilter {
+    level
+        .as_deref()
+        .and_then(|level| LevelFilter::from_str(level).ok())
+        .unwrap_or(LevelFilter::Info)
+}
+
+/// Writes each record as a JSON object on its own line.
+///
+/// Key-value pairs of the record, such as `info!(migration = name; "...")`,
+/// become fields of the object, next to the timestamp, level, target and message.
+#[derive(Debug)]
+struct JsonLinesEncoder;
+
+/// Collects the key-value pairs of a record.
+struct Fields(Map<String, Value>);
+
+impl<'kvs> VisitSource<'kvs> for Fields {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: kv::Value<'kvs>) -> Result<(), kv::Error> {
+        let value = if let Some(value) = value.to_bool() {
+            json!(value)
+        } else if let Some(value) = value.to_i64() {
+            json!(value)
+        } else if let Some(value) = value.to_u64() {
+            json!(value)
+        } else if let Some(value) = value.to_f64() {
+            json!(value)
+        } else {
+            json!(value.to_string())
+        };
+        self.0.insert(key.to_string(), value);
+        Ok(())
+    }
+}
+
+impl Encode for JsonLinesEncoder {
+    fn encode(&self, w: &mut dyn encode::Write, record: &Record) -> Result<()> {
+        let mut fields = Fields(Map::new());
+        record.key_values().visit(&mut fields)?;
+        let mut line = Map::from_iter([
+            (
+                "timestamp".to_string(),
+                json!(chrono::Local::now().format(JSON_TIMESTAMP).to_string()),
+            ),
+            ("level".to_string(), json!(record.level().as_str())),
+            ("target".to_string(), json!(record.target())),
+            ("message".to_string(), json!(record.args().to_string())),
+        ]);
+        // Fields can't replace the record's own.
+        for (key, value) in fields.0 {
+            line.entry(key).or_insert(value);
+        }
+        serde_json::to_writer(&mut *w, &line)?;
+        w.write_all(b"\n")?;
+        Ok(())
+    }
+}
+
+/// Log line encoder for the configured format.
+fn encoder(log: &Log) -> Box<dyn Encode> {
+    match log.format.as_deref() {
+        Some("json") => Box::new(JsonLinesEncoder),
+        _ => Box::new(PatternEncoder::new(PATTERN)),
+    }
+}
+
+/// Log file path. Relative paths are relative to the data directory.
+pub fn file_path(log: &Log, memospot_data: &Path) -> PathBuf {
+    let Some(file) = log.file.as_deref().filter(|file| !file.trim().is_empty()) else {
+        return memospot_data.join(Log::DEFAULT_FILE);
+    };
+    let path = Path::new(file)
+        .expand_home()
+        .unwrap_or_else(|_| PathBuf::from(file));
+    if path.is_absolute() {
+        return path;
+    }
+    absolute_path(memospot_data.join(&path)).unwrap_or(path)
+}
+
+/// Build the log4rs configuration from the log settings.
+///
+/// With logging disabled, nothing is logged.
+pub fn build_config(log: &Log, memospot_data: &Path) -> Result<log4rs::Config> {
+    if !log.enabled.unwrap_or_default() {
+        return Ok(log4rs::Config::builder().build(Root::builder().build(LevelFilter::Off))?);
+    }
+
+    let path = file_path(log, memospot_data);
+    let archive = format!("{}.{{}}.gz", path.to_string_lossy());
+    let roller = FixedWindowRoller::builder()
+        .base(1)
+        .build(&archive, log.rotate_count.unwrap_or(5))?;
+    let trigger = SizeTrigger::new(log.rotate_size.unwrap_or(10).max(1) * 1024 * 1024);
+    let policy = CompoundPolicy::new(Box::new(trigger), Box::new(roller));
+    let file = RollingFileAppender::builder()
+        .encoder(encoder(log))
+        .build(&path, Box::new(policy))
+        .with_context(|| format!("unable to open log file `{}`", path.to_string_lossy()))?;
+
+    let mut builder =
+        log4rs::Config::builder().appender(Appender::builder().build("file", Box::new(file)));
+    let mut root = Root::builder().appender("file");
+    if log.console.unwrap_or_default() {
+        let console = ConsoleAppender::builder().encoder(encoder(log)).build();
+        builder = builder.appender(Appender::builder().build("console", Box::new(console)));
+        root = root.appender("console");
+    }
+    for (target, target_level) in log.targets.iter().flatten() {
+        builder =
+            builder.logger(Logger::builder().build(target, level(&Some(target_level.clone()))));
+    }
+    Ok(builder.build(root.build(level(&log.level)))?)
+}
+
+/// Apply the log settings, setting up the logger on first use.
+///
+/// Returns true if logging is enabled.
+pub fn apply(rtcfg: &RuntimeConfig) -> Result<bool> {
+    let log = &rtcfg.yaml.memospot.log;
+    let enabled = log.enabled.unwrap_or_default();
+    if !enabled && HANDLE.get().is_none() {
+        return Ok(false);
+    }
+
+    let config = build_config(log, &rtcfg.paths.memospot_data)?;
+    match HANDLE.get() {
+        Some(handle) => handle.set_config(config),
+        None => {
+            let handle = log4rs::init_config(config)?;
+            HANDLE.set(handle).ok();
+        }
+    }
+    Ok(enabled)
+}
+
+/// Move the legacy `logging_config.yaml` file out of the way, as it's no longer read.
+///
+/// The file is removed if Memospot created it, and renamed to `logging_config.yaml.old`
+/// otherwise. Returns the new path of a customized file, whose settings must be moved
+/// to `memospot.log` by hand.
+pub fn retire_legacy_config(memospot_data: &Path) -> Result<Option<PathBuf>> {
+    let path = memospot_data.join(LEGACY_CONFIG_FILE);
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let contents = fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read `{}`", path.to_string_lossy()))?;
+    if contents.trim() == LEGACY_CONFIG_DEFAULT {
+        fs::remove_file(&path)
+            .with_context(|| format!("Failed to remove `{}`", path.to_string_lossy()))?;
+        return Ok(None);
+    }
+    let renamed = path.with_extension("yaml.old");
+    fs::rename(&path, &renamed)
+        .with_context(|| format!("Failed to rename `{}`", path.to_string_lossy()))?;
+    Ok(Some(renamed))
+}
+
+/// A current or rotated log file.
+#[derive(Debug, Clone, Serialize)]
+pub struct LogFile {
+    /// File name, such as `memospot.log` or `memospot.log.1.gz`.
+    pub name: String,
+    /// File size, in bytes.
+    pub size: u64,
+    /// Whether this is a rotated, compressed file.
+    pub rotated: bool,
+}
+
+/// A log record, read back from a log file.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct LogEntry {
+    /// Time of the record, as written in the file.
+    pub timestamp: String,
+    /// Level, such as `INFO`. Empty if the line couldn't be parsed.
+    pub level: String,
+    /// Target, such as `memospot::init` or `memos`.
+    pub target: String,
+    /// Message, including any following lines.
+    pub message: String,
+    /// Structured fields. Only JSON log lines have them.
+    pub fields: Map<String, Value>,
+}
+
+/// List the current log file, followed by the rotated ones, newest first.
+pub fn files(log: &Log, memospot_data: &Path) -> Vec<(LogFile, PathBuf)> {
+    let path = file_path(log, memospot_data);
+    let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+        return Vec::new();
+    };
+    let name = name.to_string_lossy().to_string();
+    let mut files: Vec<(u32, PathBuf)> = Vec::new();
+    if path.is_file() {
+        files.push((0, path.clone()));
+    }
+    // Rotated files are named `{name}.{index}.gz`, `1` being the newest.
+    for entry in fs::read_dir(dir).into_iter().flatten().flatten() {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        let index = file_name
+            .strip_prefix(&format!("{}.", name))
+            .and_then(|rest| rest.strip_suffix(".gz"))
+            .and_then(|index| index.parse::<u32>().ok());
+        if let Some(index) = index.filter(|index| *index > 0) {
+            files.push((index, entry.path()));
+        }
+    }
+    files.sort_by_key(|(index, _)| *index);
+
+    files
+        .into_iter()
+        .map(|(index, path)| {
+            let file = LogFile {
+                name: path
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .to_string(),
+                size: fs::metadata(&path).map(|m| m.len()).unwrap_or_default(),
+                rotated: index > 0,
+            };
+            (file, path)
+        })
+        .collect()
+}
+
+/// Read a log file, decompressing rotated files.
+pub fn read(path: &Path) -> Result<String> {
+    let mut bytes = Vec::new();
+    let file = fs::File::open(path)
+        .with_context(|| format!("unable to open log file `{}`", path.to_string_lossy()))?;
+    if path.extension().is_some_and(|ext| ext == "gz") {
+        GzDecoder::new(file).read_to_end(&mut bytes)?;
+    } else {
+        std::io::BufReader::new(file).read_to_end(&mut bytes)?;
+    }
+    Ok(String::from_utf8_lossy(&bytes).to_string())
+}
+
+/// Read the last `lines` records of a log file matching `level` and `target`.
+///
+/// The current log file is read from the end, in growing chunks, until enough
+/// records are found. Rotated files are compressed, and read whole.
+pub fn tail(
+    path: &Path,
+    lines: usize,
+    level: Option<&str>,
+    target: Option<&str>,
+) -> Result<Vec<LogEntry>> {
+    let filter = |entries: Vec<LogEntry>| -> Vec<LogEntry> {
+        entries
+            .into_iter()
+            .filter(|entry| matches(entry, level, target))
+            .collect()
+    };
+    let mut entries = if path.extension().is_some_and(|ext| ext == "gz") {
+        filter(parse(&read(path)?))
+    } else {
+        let mut file = fs::File::open(path)
+            .with_context(|| format!("unable to open log file `{}`", path.to_string_lossy()))?;
+        let size = file.metadata()?.len();
+        let mut chunk = TAIL_CHUNK;
+        loop {
+            let start = size.saturating_sub(chunk);
+            let mut bytes = Vec::new();
+            file.seek(SeekFrom::Start(start))?;
+            (&mut file).take(size - start).read_to_end(&mut bytes)?;
+            let contents = String::from_utf8_lossy(&bytes);
+            let mut contents: &str = &contents;
+            if start > 0 {
+                // Skip the line the chunk starts in the middle of.
+                contents = contents.split_once('\n').map_or("", |(_, rest)| rest);
+            }
+            let mut parsed = parse(contents);
+            if start > 0 {
+                // Leading lines may continue a record that starts before the chunk.
+                let first = parsed
+                    .iter()
+                    .position(|entry| !entry.level.is_empty())
+                    .unwrap_or(parsed.len());
+                parsed.drain(..first);
+            }
+            let entries = filter(parsed);
+            if start == 0 || entries.len() >= lines {
+                break entries;
+            }
+            chunk *= 2;
+        }
+    };
+    let skip = entries.len().saturating_sub(lines);
+    Ok(entries.split_off(skip))
+}
+
+/// Parse a JSON log line.
+fn parse_json(line: &str) -> Option<LogEntry> {
+    let Ok(Value::Object(mut fields)) = serde_json::from_str::<Value>(line) else {
+        return None;
+    };
+    let mut take = |key: &str| match fields.remove(key) {
+        Some(Value::String(value)) => Some(value),
+        _ => None,
+    };
+    Some(LogEntry {
+        timestamp: take("timestamp")?,
+        level: take("level")?,
+        target: take("target").unwrap_or_default(),
+        message: take("message").unwrap_or_default(),
+        fields,
+    })
+}
+
+/// Parse a text log line, laid out as `PATTERN`.
+fn parse_text(line: &str) -> Option<LogEntry> {
+    let timestamp = line.get(..19)?;
+    chrono::NaiveDateTime::parse_from_str(timestamp, TEXT_TIMESTAMP).ok()?;
+    let rest = line[19..].strip_prefix(" - ")?;
+    let (level, rest) = rest.split_once(" [")?;
+    Level::from_str(level).ok()?;
+    let (target, message) = rest.split_once("]: ")?;
+    Some(LogEntry {
+        timestamp: timestamp.to_string(),
+        level: level.to_string(),
+        target: target.to_string(),
+        message: message.to_string(),
+        fields: Map::new(),
+    })
+}
+
+/// Parse log file contents, in either format.
+///
+/// Lines that don't start a record, such as the rest of a multi-line message,
+/// are added to the previous record.
+pub fn parse(contents: &str) -> Vec<LogEntry> {
+    let mut entries: Vec<LogEntry> = Vec::new();
+    for line in contents.lines() {
+        if let Some(entry) = parse_json(line).or_else(|| parse_text(line)) {
+            entries.push(entry);
+            continue;
+        }
+        match entries.last_mut() {
+            Some(last) => {
+                last.message.push('\n');
+                last.message.push_str(line);
+            }
+            None if !line.trim().is_empty() => entries.push(LogEntry {
+                message: line.to_string(),
+                ..Default::default()
+            }),
+            None => {}
+        }
+    }
+    entries
+}
+
+/// Whether an entry is at least as severe as `level`, and logged by `target`
+/// or one of its modules.
+pub fn matches(entry: &LogEntry, level: Option<&str>, target: Option<&str>) -> bool {
+    if let Some(level) = level.and_then(|level| LevelFilter::from_str(level).ok()) {
+        match Level::from_str(&entry.level) {
+            Ok(entry_level) if entry_level <= level => {}
+            _ => return false,
+        }
+    }
+    match target.map(str::trim).filter(|target| !target.is_empty()) {
+        Some(target) => {
+            entry.target == target || entry.target.starts_with(&format!("{}::", target))
+        }
+        None => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log4rs::encode::writer::simple::SimpleWriter;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_json_lines_encoder() {
+        let fields: [(&str, kv::Value); 5] = [
+            ("retried", true.into()),
+            ("count", (-3i64).into()),
+            ("ratio", 0.5.into()),
+            ("name", "a \"quoted\" name".into()),
+            ("level", "ignored".into()),
+        ];
+        let mut writer = SimpleWriter(Vec::new());
+        JsonLinesEncoder
+            .encode(
+                &mut writer,
+                &Record::builder()
+                    .level(Level::Warn)
+                    .target("memospot::init")
+                    .args(format_args!("said \"hi\"\nand left"))
+                    .key_values(&fields)
+                    .build(),
+            )
+            .unwrap();
+
+        let output = String::from_utf8(writer.0).unwrap();
+        let line = output.strip_suffix('\n').unwrap();
+        assert!(!line.contains('\n'));
+        assert!(line.contains(r#""message":"said \"hi\"\nand left""#));
+
+        let Value::Object(mut object) = serde_json::from_str(line).unwrap() else {
+            panic!("not a JSON object: {}", line);
+        };
+        let timestamp = object.remove("timestamp").unwrap();
+        assert!(
+            chrono::DateTime::parse_from_str(timestamp.as_str().unwrap(), JSON_TIMESTAMP)
+                .is_ok()
+        );
+        assert_eq!(
+            Value::Object(object),
+            json!({
+                "level": "WARN",
+                "target": "memospot::init",
+                "message": "said \"hi\"\nand left",
+                "retried": true,
+                "count": -3,
+                "ratio": 0.5,
+                "name": "a \"quoted\" name",
+            })
+        );
+    }
+
+    #[test]
+    fn test_build_config() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let mut log = config::Config::default().memospot.log;
+
+        let disabled = build_config(&log, tmp_dir.path()).unwrap();
+        assert_eq!(disabled.root().level(), LevelFilter::Off);
+        assert!(disabled.appenders().is_empty());
+        assert!(!tmp_dir.path().join(Log::DEFAULT_FILE).exists());
+
+        log.enabled = Some(true);
+        log.level = Some("debug".to_string());
+        log.console = Some(true);
+        log.rotate_count = Some(1);
+        log.targets = Some(BTreeMap::from([("memos".to_string(), "warn".to_string())]));
+        let enabled = build_config(&log, tmp_dir.path()).unwrap();
+        assert_eq!(enabled.root().level(), LevelFilter::Debug);
+        assert_eq!(enabled.root().appenders(), ["file", "console"]);
+        let logger = &enabled.loggers()[0];
+        assert_eq!(
+            (logger.name(), logger.level()),
+            ("memos", LevelFilter::Warn)
+        );
+        assert!(tmp_dir.path().join(Log::DEFAULT_FILE).is_file());
+    }
+
+    #[test]
+    fn test_retire_legacy_config() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let path = tmp_dir.path().join(LEGACY_CONFIG_FILE);
+        let renamed = tmp_dir.path().join("logging_config.yaml.old");
+        assert_eq!(retire_legacy_config(tmp_dir.path()).unwrap(), None);
+
+        // The file Memospot created is removed.
+        fs::write(&path, format!("{}\n", LEGACY_CONFIG_DEFAULT)).unwrap();
+        assert_eq!(retire_legacy_config(tmp_dir.path()).unwrap(), None);
+        assert!(!path.exists());
+        assert!(!renamed.exists());
+
+        // A customized file is renamed, once.
+        let customized = LEGACY_CONFIG_DEFAULT.replace("level: info", "level: debug");
+        fs::write(&path, &customized).unwrap();
+        assert_eq!(
+            retire_legacy_config(tmp_dir.path()).unwrap(),
+            Some(renamed.clone())
+        );
+        assert!(!path.exists());
+        assert_eq!(retire_legacy_config(tmp_dir.path()).unwrap(), None);
+        assert_eq!(fs::read_to_string(&renamed).unwrap(), customized);
+    }
+
+    fn entry(level: &str, target: &str) -> LogEntry {
+        LogEntry {
+            level: level.to_string(),
+            target: target.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let contents = concat!(
+            "orphan line\n",
+            "2024-05-01 10:00:00 - INFO [memospot::init]: Starting\n",
+            "  continued\n",
+            "\n",
+            r#"{"timestamp":"2024-05-01T10:00:01.000+00:00","level":"WARN","target":"memos","message":"slow","ms":250}"#,
+            "\n",
+            "2024-05-01 10:00:02 - BOGUS [memos]: not a record\n",
+        );
+        let entries = parse(contents);
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].level, "");
+        assert_eq!(entries[0].message, "orphan line");
+        assert_eq!(
+            entries[1],
+            LogEntry {
+                timestamp: "2024-05-01 10:00:00".to_string(),
+                level: "INFO".to_string(),
+                target: "memospot::init".to_string(),
+                message: "Starting\n  continued\n".to_string(),
+                fields: Map::new(),
+            }
+        );
+        assert_eq!(
+            entries[2],
+            LogEntry {
+                timestamp: "2024-05-01T10:00:01.000+00:00".to_string(),
+                level: "WARN".to_string(),
+                target: "memos".to_string(),
+                message: "slow\n2024-05-01 10:00:02 - BOGUS [memos]: not a record".to_string(),
+                fields: Map::from_iter([("ms".to_string(), json!(250))]),
+            }
+        );
+    }
+
+    #[test]
+    fn test_matches() {
+        let warn = entry("WARN", "memos");
+        assert!(matches(&warn, None, None));
+        assert!(matches(&warn, Some("info"), None));
+        assert!(matches(&warn, Some("warn"), Some(" memos ")));
+        assert!(!matches(&warn, Some("error"), None));
+        assert!(!matches(&warn, Some("info"), Some("memospot")));
+        assert!(!matches(&entry("", "memos"), Some("trace"), None));
+        // An unknown level doesn't filter.
+        assert!(matches(&warn, Some("loud"), Some("")));
+
+        let module = entry("DEBUG", "memospot::init");
+        assert!(matches(&module, None, Some("memospot")));
+        assert!(matches(&module, None, Some("memospot::init")));
+        assert!(!matches(&module, None, Some("memos")));
+        assert!(!matches(&module, None, Some("memospot::in")));
+    }
+
+    #[test]
+    fn test_tail() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let path = tmp_dir.path().join(Log::DEFAULT_FILE);
+        let mut contents = String::new();
+        for i in 0..5000 {
+            let target = if i == 10 { "memos" } else { "memospot::init" };
+            contents.push_str(&format!(
+                "2024-05-01 10:00:00 - INFO [{}]: Record {}\n  continued\n",
+                target, i
+            ));
+        }
+        fs::write(&path, &contents).unwrap();
+        assert!(contents.len() as u64 > TAIL_CHUNK * 2);
+
+        let entries = tail(&path, 3, None, None).unwrap();
+        let messages: Vec<&str> = entries.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "Record 4997\n  continued",
+                "Record 4998\n  continued",
+                "Record 4999\n  continued"
+            ]
+        );
+
+        // Records are looked up further back until enough of them match.
+        let entries = tail(&path, 3, Some("info"), Some("memos")).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].message, "Record 10\n  continued");
+
+        // Every record read from a chunk is complete.
+        let entries = tail(&path, 2000, None, None).unwrap();
+        assert_eq!(entries.len(), 2000);
+        assert!(entries
+            .iter()
+            .all(|e| e.level == "INFO" && e.message.ends_with("\n  continued")));
+        assert_eq!(entries[0].message, "Record 3000\n  continued");
+    }
+}
@@ -1,29 +1,47 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod cli;
 mod init;
 mod js_handler;
+mod logging;
 mod memos;
+mod network;
+mod reload;
+mod relocate;
+mod remote;
 mod runtime_config;
+mod secrets;
 mod sqlite;
+mod support;
+mod updater;
 mod webview;
 mod zip;
 
 use crate::runtime_config::{RuntimeConfig, RuntimeConfigPaths};
-use config::Config;
+use config::{Config, ConfigLayers};
 use memospot::*;
 
-use log::{debug, info};
+use log::{debug, info, warn};
 use std::{ops::IndexMut, path::PathBuf};
 use tauri::Manager;
 
 #[warn(unused_extern_crates)]
 fn main() {
-    init::ensure_webview();
-
+    let args = cli::Args::parse();
     let memospot_data = init::data_path("memospot");
-    let config_path = memospot_data.join("memospot.yaml");
-    let yaml_config = init::config(&config_path);
+    let config_path = init::config_path(&args, &memospot_data);
+    let config_layers = ConfigLayers {
+        profile: args.profile.clone(),
+        ..ConfigLayers::discover(&config_path, &ConfigLayers::system_dir())
+    };
+    if let Some(command) = args.command {
+        cli::run(command, &config_layers);
+        return;
+    }
+    let yaml_config = init::config(&config_layers);
+
+    init::ensure_webview(&yaml_config.memospot.network.proxy);
 
     let mut rtcfg = RuntimeConfig {
         paths: RuntimeConfigPaths {
@@ -33,11 +51,13 @@ fn main() {
             _memospot_backups: PathBuf::new(),
             memospot_bin: PathBuf::new(),
             memospot_config_file: config_path.clone(),
+            memospot_config_layers: config_layers.clone(),
             memospot_cwd: PathBuf::new(),
             memospot_data: memospot_data.clone(),
             _memospot_resources: PathBuf::new(),
         },
         managed_server: true,
+        offline_fallback: false,
         memos_url: String::new(),
         yaml: yaml_config.clone(),
         __yaml__: yaml_config,
@@ -53,10 +73,7 @@ fn main() {
     );
     info!("Memos URL: {}", rtcfg.memos_url);
 
-    rtcfg.managed_server = rtcfg.memos_url.starts_with(&format!(
-        "http://localhost:{}",
-        rtcfg.yaml.memos.port.unwrap_or_default()
-    ));
+    rtcfg.managed_server = rtcfg.selects_managed_server();
 
     init::setup_logger(&rtcfg);
 
@@ -65,16 +82,33 @@ fn main() {
         "Memospot data path: {}",
         rtcfg.paths.memospot_data.to_string_lossy()
     );
+    for (layer, path) in [
+        ("System configuration", &config_layers.system),
+        ("Configuration policy", &config_layers.policy),
+    ] {
+        if let Some(path) = path {
+            info!("{}: {}", layer, path.to_string_lossy());
+        }
+    }
 
+    init::env_overrides();
+    init::access_tokens(&mut rtcfg);
+    init::secrets(&rtcfg);
+    init::remote_server(&mut rtcfg);
     rtcfg.paths._memospot_backups = init::backup_directory(&rtcfg);
     rtcfg.paths.memospot_bin = std::env::current_exe().unwrap();
     rtcfg.paths.memospot_cwd = rtcfg.paths.memospot_bin.parent().unwrap().to_path_buf();
     rtcfg.paths.memos_bin = init::find_memos(&rtcfg);
 
+    // The built-in updater can't be given a proxy, so updates are checked
+    // by `updater::check` instead when one is configured.
+    let updater_enabled = rtcfg.yaml.memospot.updater.enabled.unwrap_or_default();
+    let proxied_updater =
+        updater_enabled && network::has_proxy(&rtcfg.yaml.memospot.network.proxy);
+
     let mut tauri_ctx = tauri::generate_context!();
     let app_version = tauri_ctx.package_info().version.to_string();
-    tauri_ctx.config_mut().tauri.updater.active =
-        rtcfg.yaml.memospot.updater.enabled.unwrap_or_default();
+    tauri_ctx.config_mut().tauri.updater.active = updater_enabled && !proxied_updater;
 
     if !tauri_ctx.config_mut().tauri.windows.is_empty() {
         let base_user_agent = "Mozilla/5.0 (x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36";
@@ -89,38 +123,80 @@ fn main() {
         window_config.y = Some(rtcfg.yaml.memospot.window.y.unwrap_or_default() as f64);
         window_config.user_agent =
             Some(format!("{} Memospot/{}", base_user_agent, &app_version));
-        window_config.title = format!("Memospot {}", &app_version);
+        window_config.title = rtcfg.window_title(&app_version);
     }
 
     let mut rtcfg_setup = rtcfg.clone();
     let Ok(tauri_app) = tauri::Builder::default()
         .manage(js_handler::MemosURL::manage(rtcfg.memos_url.clone()))
+        .manage(js_handler::RuntimeConfigState::manage(rtcfg.clone()))
         .invoke_handler(tauri::generate_handler![
             js_handler::get_memos_url,
-            js_handler::get_env
+            js_handler::get_env,
+            js_handler::scan_assets,
+            js_handler::relocate_memos_data,
+            js_handler::switch_workspace,
+            js_handler::switch_remote_profile,
+            js_handler::set_secret,
+            js_handler::get_config_origins,
+            js_handler::export_settings,
+            js_handler::import_settings,
+            js_handler::list_log_files,
+            js_handler::tail_log,
+            js_handler::open_log_window,
+            js_handler::create_support_bundle
         ])
+        .on_page_load(|window, payload| {
+            // Seed the session of remote Memos servers with the stored access token.
+            let rtcfg = window
+                .state::<js_handler::RuntimeConfigState>()
+                .0
+                .blocking_lock()
+                .clone();
+            if rtcfg.managed_server || !payload.url().starts_with(&rtcfg.memos_url) {
+                return;
+            }
+            if let Some(token) = remote::access_token(&rtcfg.yaml.memospot.remote) {
+                window
+                    .eval(&remote::session_script(&token))
+                    .unwrap_or_default();
+            }
+        })
         .setup(move |app| {
+            // Add Tauri resource directory as `_memospot_resources`.
+            rtcfg_setup.paths._memospot_resources = app.path_resolver().resource_dir().unwrap();
+            app.state::<js_handler::RuntimeConfigState>()
+                .0
+                .blocking_lock()
+                .paths
+                ._memospot_resources
+                .clone_from(&rtcfg_setup.paths._memospot_resources);
+
+            reload::watch(
+                app.handle(),
+                rtcfg_setup.paths.memospot_config_layers.clone(),
+            );
+
+            if proxied_updater {
+                tauri::async_runtime::spawn(updater::check(
+                    app.handle(),
+                    rtcfg_setup.yaml.memospot.network.proxy.clone(),
+                ));
+            }
+
             if !rtcfg_setup.managed_server {
                 info!(
                     "Using custom Memos address: {}. Memos server will not be started.",
                     rtcfg_setup.memos_url
                 );
-                let title_url = rtcfg_setup
-                    .memos_url
-                    .trim_start_matches("http://")
-                    .trim_start_matches("https://")
-                    .trim_end_matches("/");
                 if let Some(main_window) = app.get_window("main") {
                     main_window
-                        .set_title(&format!("Memospot {} - {}", app_version, title_url))
+                        .set_title(&rtcfg_setup.window_title(&app_version))
                         .unwrap_or_default();
                 }
                 return Ok(());
             }
 
-            // Add Tauri resource directory as `_memospot_resources`.
-            rtcfg_setup.paths._memospot_resources = app.path_resolver().resource_dir().unwrap();
-
             tauri::async_runtime::spawn(async move {
                 init::migrate_database(&rtcfg_setup).await;
 
@@ -136,6 +212,8 @@ fn main() {
     };
 
     tauri_app.run(move |app_handle, event| {
+        // Runtime configuration may be updated by JS handlers.
+        let state = app_handle.state::<js_handler::RuntimeConfigState>();
         match event {
             tauri::RunEvent::WindowEvent { label, event, .. } => {
                 if label != "main" {
@@ -143,6 +221,7 @@ fn main() {
                 }
                 if let tauri::WindowEvent::Resized { .. } = event {
                     let main_window = app_handle.get_window("main").unwrap();
+                    let mut rtcfg = state.0.blocking_lock();
                     rtcfg.yaml.memospot.window.maximized =
                         Some(main_window.is_maximized().unwrap_or_default());
                     rtcfg.yaml.memospot.window.width =
@@ -157,10 +236,17 @@ fn main() {
             }
             tauri::RunEvent::ExitRequested { api, .. } => {
                 api.prevent_exit();
-                // Save the config file, if it has changed.
+                let rtcfg = state.0.blocking_lock().clone();
+                // Save the config file, if it has changed, keeping external edits.
                 if rtcfg.yaml != rtcfg.__yaml__ {
                     info!("Configuration has changed. Saving…");
-                    if let Err(e) = Config::save_file(&config_path, &rtcfg.yaml) {
+                    let file_config = config_layers.extract().unwrap_or_else(|e| {
+                        warn!("Unable to read the configuration file: {}", e);
+                        rtcfg.__yaml__.clone()
+                    });
+                    let saved = Config::merge(&rtcfg.__yaml__, &rtcfg.yaml, &file_config)
+                        .and_then(|merged| config_layers.save(&merged));
+                    if let Err(e) = saved {
                         error_dialog!(
                             "Failed to save config file:\n`{}`\n\n{}",
                             config_path.to_string_lossy(),
@@ -1,13 +1,21 @@
+use config::secret::{self, REDACTED};
 use homedir::HomeDirExt;
 use itertools::Itertools;
-use log::{debug, info};
+use log::{debug, error, info, warn};
 use memospot::absolute_path;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
-use std::io::{Error, ErrorKind, Result};
+use std::io::Result;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::api::process::CommandEvent;
+use tokio::time::Instant;
 
 use crate::RuntimeConfig;
+use crate::{network, remote, secrets};
+
+/// Log target of the Memos server output.
+pub const MEMOS_LOG_TARGET: &str = "memos";
 
 /// Spawn Memos server.
 ///
@@ -16,18 +24,124 @@ pub fn spawn(rtcfg: &RuntimeConfig) -> Result<()> {
     let env_vars: HashMap<String, String> = prepare_env(rtcfg);
     let command = rtcfg.paths.memos_bin.to_string_lossy().to_string();
     let cwd = get_cwd(rtcfg);
-    debug!("Memos environment: {:#?}", env_vars);
+    debug!("Memos environment: {:#?}", redact_env(rtcfg, &env_vars));
     info!("Memos working directory: {}", cwd.to_string_lossy());
     tauri::async_runtime::spawn(async move {
-        tauri::api::process::Command::new(command)
+        let spawned = tauri::api::process::Command::new(command)
             .envs(env_vars)
             .current_dir(cwd.clone())
-            .spawn()
-            .map_err(|e| Error::new(ErrorKind::Other, e))
+            .spawn();
+        let (mut events, _child) = match spawned {
+            Ok(spawned) => spawned,
+            Err(e) => {
+                error!("Failed to spawn Memos server: {}", e);
+                return;
+            }
+        };
+        // Memos output is logged under its own target, so its level can be set apart.
+        while let Some(event) = events.recv().await {
+            match event {
+                CommandEvent::Stdout(line) => {
+                    info!(target: MEMOS_LOG_TARGET, stream = "stdout"; "{}", line.trim_end())
+                }
+                CommandEvent::Stderr(line) => {
+                    info!(target: MEMOS_LOG_TARGET, stream = "stderr"; "{}", line.trim_end())
+                }
+                CommandEvent::Error(e) => warn!(target: MEMOS_LOG_TARGET, "{}", e),
+                CommandEvent::Terminated(payload) => {
+                    info!(
+                        target: MEMOS_LOG_TARGET,
+                        exit_code:? = payload.code;
+                        "Memos server exited: {:?}",
+                        payload.code
+                    )
+                }
+                _ => {}
+            }
+        }
     });
     Ok(())
 }
 
+/// Wait until Memos server responds to health checks.
+///
+/// Returns false if the server does not respond within `timeout`.
+pub async fn wait_until_ready(rtcfg: &RuntimeConfig, timeout: Duration) -> bool {
+    let endpoint = format!("{}/healthz", rtcfg.memos_url.trim_end_matches('/'));
+    let Ok(client) = network::client_builder(&rtcfg.yaml.memospot.network.proxy)
+        .and_then(|builder| builder.timeout(Duration::from_secs(1)).build())
+    else {
+        return false;
+    };
+
+    let start_time = Instant::now();
+    while start_time.elapsed() < timeout {
+        if let Ok(response) = client.get(&endpoint).send().await {
+            if response.status().is_success() {
+                debug!("Memos server is ready after {:?}.", start_time.elapsed());
+                return true;
+            }
+        }
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+    false
+}
+
+/// Ask the Memos server for its version.
+///
+/// Returns None if the server can't be reached, or doesn't report its version.
+pub async fn version(rtcfg: &RuntimeConfig) -> Option<String> {
+    // Workspace profile endpoints, from the newest Memos release to the oldest.
+    const ENDPOINTS: [&str; 3] = [
+        "/api/v1/workspace/profile",
+        "/api/v2/workspace/profile",
+        "/api/v1/status",
+    ];
+
+    let proxy = &rtcfg.yaml.memospot.network.proxy;
+    let local_client = network::client_builder(proxy)
+        .and_then(|builder| builder.timeout(Duration::from_secs(2)).build())
+        .ok()?;
+    // Remote servers may pin their certificate, which must be checked on every response.
+    let remote_client = if rtcfg.managed_server {
+        None
+    } else {
+        let tls = rtcfg.yaml.memospot.remote.active_tls();
+        Some(remote::Client::new(tls, proxy).ok()?)
+    };
+
+    let base_url = rtcfg.memos_url.trim_end_matches('/');
+    for endpoint in ENDPOINTS {
+        let url = format!("{}{}", base_url, endpoint);
+        let response = match &remote_client {
+            Some(client) => client.get(&url).await.ok(),
+            None => local_client.get(&url).send().await.ok(),
+        };
+        let Some(response) = response else {
+            return None;
+        };
+        if !response.status().is_success() {
+            continue;
+        }
+        let Ok(profile) = serde_json::from_str::<serde_json::Value>(
+            &response.text().await.unwrap_or_default(),
+        ) else {
+            continue;
+        };
+        let version = [
+            &profile["version"],
+            &profile["workspaceProfile"]["version"],
+            &profile["profile"]["version"],
+        ]
+        .into_iter()
+        .find_map(|version| version.as_str());
+        if let Some(version) = version {
+            return Some(version.to_string());
+        }
+    }
+    None
+}
+
 /// Decide which working directory use for Memos server.
 ///
 /// The front end is not embedded from Memos v0.18.2 to v0.21.0,
@@ -113,12 +227,19 @@ pub fn prepare_env(rtcfg: &RuntimeConfig) -> HashMap<String, String> {
         ("password_auth", "true".to_string()),
     ]);
 
-    let mut env_vars: HashMap<String, String> = HashMap::new();
+    // Hand proxy settings to Memos, so its outbound requests also honor them.
+    let mut env_vars: HashMap<String, String> =
+        network::proxy_env(&rtcfg.yaml.memospot.network.proxy);
 
-    // Add user's environment variables
+    // Add user's environment variables, resolving secret references.
     if let Some(memos_env) = &rtcfg.yaml.memos.env {
         for (key, value) in memos_env {
-            env_vars.insert(make_env_key(key), value.into());
+            match secrets::resolve(value) {
+                Ok(value) => {
+                    env_vars.insert(make_env_key(key), value);
+                }
+                Err(e) => warn!("Skipping Memos environment variable `{}`: {:#}", key, e),
+            }
         }
     }
 
@@ -129,3 +250,49 @@ pub fn prepare_env(rtcfg: &RuntimeConfig) -> HashMap<String, String> {
     }
     env_vars
 }
+
+/// Values of the Memos environment hidden by [`redact_env`], to scrub them from logs.
+pub fn secret_values(rtcfg: &RuntimeConfig) -> Vec<String> {
+    let env_vars = prepare_env(rtcfg);
+    let redacted = redact_env(rtcfg, &env_vars);
+    env_vars
+        .into_iter()
+        .filter(|(key, value)| {
+            !value.is_empty() && redacted.get(key).is_some_and(|v| v == REDACTED)
+        })
+        .map(|(_, value)| value)
+        .collect()
+}
+
+/// Copy of the Memos environment that is safe to log.
+///
+/// Like [`config::Config::redacted`], redacts every variable from `memos.env`,
+/// as any of them may hold credentials. Also redacts variables with sensitive
+/// names and proxy settings carrying credentials.
+fn redact_env(
+    rtcfg: &RuntimeConfig,
+    env_vars: &HashMap<String, String>,
+) -> BTreeMap<String, String> {
+    let from_user: Vec<String> = rtcfg
+        .yaml
+        .memos
+        .env
+        .iter()
+        .flatten()
+        .map(|(key, _)| make_env_key(key))
+        .collect();
+    let proxy_credentials = network::has_credentials(&rtcfg.yaml.memospot.network.proxy);
+
+    env_vars
+        .iter()
+        .map(|(key, value)| {
+            let upper_key = key.to_uppercase();
+            let is_proxy = upper_key.ends_with("_PROXY") && upper_key != "NO_PROXY";
+            let redact = from_user.contains(key)
+                || secret::is_sensitive(key)
+                || (is_proxy && proxy_credentials);
+            let value = if redact { REDACTED } else { value.as_str() };
+            (key.clone(), value.to_string())
+        })
+        .collect()
+}
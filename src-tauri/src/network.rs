@@ -0,0 +1,101 @@
+//! Outbound network settings.
+
+use crate::secrets;
+use config::secret::redact_url;
+use config::Proxy;
+use log::warn;
+use std::collections::HashMap;
+
+/// Hosts that never go through the proxy.
+const LOCAL_HOSTS: &str = "localhost,127.0.0.1,::1";
+
+/// Trim an optional setting, treating empty values as unset.
+fn non_empty(value: &Option<String>) -> Option<&str> {
+    value.as_deref().map(str::trim).filter(|v| !v.is_empty())
+}
+
+/// Whether a proxy is configured.
+pub fn has_proxy(proxy: &Proxy) -> bool {
+    non_empty(&proxy.url).is_some()
+}
+
+/// Whether the proxy has credentials, either as settings or in its URL.
+pub fn has_credentials(proxy: &Proxy) -> bool {
+    non_empty(&proxy.username).is_some()
+        || non_empty(&proxy.url).is_some_and(|url| redact_url(url) != url)
+}
+
+/// Password written in the proxy URL, as it appears there.
+pub fn url_password(proxy: &Proxy) -> Option<String> {
+    let url = reqwest::Url::parse(non_empty(&proxy.url)?).ok()?;
+    url.password().map(str::to_string)
+}
+
+/// Hosts that bypass the proxy, including local addresses.
+fn no_proxy(proxy: &Proxy) -> String {
+    match non_empty(&proxy.no_proxy) {
+        Some(hosts) => format!("{},{}", LOCAL_HOSTS, hosts),
+        None => LOCAL_HOSTS.to_string(),
+    }
+}
+
+/// Build an HTTP client with the configured proxy.
+///
+/// Without a configured proxy, the system proxy environment variables are used.
+pub fn client_builder(proxy: &Proxy) -> reqwest::Result<reqwest::ClientBuilder> {
+    let builder = reqwest::Client::builder();
+    let Some(url) = non_empty(&proxy.url) else {
+        return Ok(builder);
+    };
+
+    let mut reqwest_proxy =
+        reqwest::Proxy::all(url)?.no_proxy(reqwest::NoProxy::from_string(&no_proxy(proxy)));
+    if let Some(username) = non_empty(&proxy.username) {
+        let password = secrets::resolve_optional(&proxy.password).unwrap_or_default();
+        reqwest_proxy = reqwest_proxy.basic_auth(username, &password);
+    }
+    Ok(builder.proxy(reqwest_proxy))
+}
+
+/// Proxy environment variables, as understood by Memos and most tools.
+///
+/// Credentials are embedded in the proxy URL, so these values must not be logged,
+/// and must only be passed to child processes, never set for Memospot itself.
+pub fn proxy_env(proxy: &Proxy) -> HashMap<String, String> {
+    let mut env_vars: HashMap<String, String> = HashMap::new();
+    let Some(url) = non_empty(&proxy.url) else {
+        return env_vars;
+    };
+
+    let mut proxy_url = match reqwest::Url::parse(url) {
+        Ok(proxy_url) => proxy_url,
+        Err(e) => {
+            warn!("Ignoring invalid proxy URL `{}`: {}", redact_url(url), e);
+            return env_vars;
+        }
+    };
+    if let Some(username) = non_empty(&proxy.username) {
+        let password = secrets::resolve_optional(&proxy.password);
+        if proxy_url.set_username(username).is_err()
+            || proxy_url.set_password(password.as_deref()).is_err()
+        {
+            warn!(
+                "Proxy URL `{}` does not support credentials.",
+                redact_url(url)
+            );
+        }
+    }
+
+    let proxy_url = proxy_url.to_string();
+    let no_proxy = no_proxy(proxy);
+    for (key, value) in [
+        ("HTTP_PROXY", &proxy_url),
+        ("HTTPS_PROXY", &proxy_url),
+        ("ALL_PROXY", &proxy_url),
+        ("NO_PROXY", &no_proxy),
+    ] {
+        env_vars.insert(key.to_lowercase(), value.clone());
+        env_vars.insert(key.to_string(), value.clone());
+    }
+    env_vars
+}
@@ -0,0 +1,296 @@
+//! Hot reload of the configuration file.
+//!
+//! Changes made to `memospot.yaml` while the app is running are merged into
+//! the runtime configuration. Settings that can change live are applied right
+//! away; the others are reported and take effect on the next launch.
+
+use crate::js_handler::{self, RuntimeConfigState};
+use crate::runtime_config::RuntimeConfig;
+use crate::{init, logging};
+use config::{Config, ConfigLayers, Window};
+use log::{debug, info, warn};
+use notify_debouncer_mini::notify::RecursiveMode;
+use notify_debouncer_mini::{new_debouncer, DebounceEventResult};
+use serde::Serialize;
+use std::path::Path;
+use std::sync::mpsc;
+use std::time::Duration;
+use tauri::{AppHandle, LogicalPosition, LogicalSize, Manager};
+
+/// Settings applied without a restart, by key prefix.
+const LIVE_KEYS: &[&str] = &[
+    "memospot.backups.enabled",
+    "memospot.log",
+    "memospot.remote.default",
+    "memospot.window",
+];
+
+/// Event emitted to the front end after the configuration is reloaded.
+const RELOADED_EVENT: &str = "memospot://config-reloaded";
+
+/// Keys changed by a configuration reload.
+#[derive(Debug, Clone, Serialize)]
+struct Reloaded {
+    /// Keys applied right away.
+    applied: Vec<String>,
+    /// Keys that take effect on the next launch.
+    restart_required: Vec<String>,
+}
+
+fn is_live(key: &str) -> bool {
+    LIVE_KEYS
+        .iter()
+        .any(|live| key == *live || key.starts_with(&format!("{}.", live)))
+}
+
+/// Split changed keys into the ones applied right away and the ones requiring a restart.
+fn split_live(changed: Vec<String>) -> (Vec<String>, Vec<String>) {
+    changed.into_iter().partition(|key| is_live(key))
+}
+
+/// Watch the user configuration file for changes, from a background thread.
+///
+/// The parent directory is watched, as most editors replace files instead of writing to them.
+pub fn watch(app_handle: AppHandle, layers: ConfigLayers) {
+    let config_path = layers.user.clone();
+    let Some(config_dir) = config_path.parent().map(Path::to_path_buf) else {
+        return;
+    };
+
+    std::thread::spawn(move || {
+        let (tx, rx) = mpsc::channel::<DebounceEventResult>();
+        let mut debouncer = match new_debouncer(Duration::from_millis(300), tx) {
+            Ok(debouncer) => debouncer,
+            Err(e) => {
+                warn!("Unable to watch the configuration file: {}", e);
+                return;
+            }
+        };
+        if let Err(e) = debouncer
+            .watcher()
+            .watch(&config_dir, RecursiveMode::NonRecursive)
+        {
+            warn!("Unable to watch the configuration file: {}", e);
+            return;
+        }
+
+        for result in rx {
+            match result {
+                Ok(events) => {
+                    if events
+                        .iter()
+                        .any(|e| e.path.file_name() == config_path.file_name())
+                    {
+                        tauri::async_runtime::block_on(reload(&app_handle, &layers));
+                    }
+                }
+                Err(e) => warn!("Configuration file watcher error: {}", e),
+            }
+        }
+    });
+}
+
+/// Reload the configuration file and apply the changes.
+///
+/// Invalid files are ignored until fixed. Files written by the app itself
+/// match the last known configuration and are ignored as well.
+async fn reload(app_handle: &AppHandle, layers: &ConfigLayers) {
+    if !layers.user.is_file() {
+        return;
+    }
+    let mut file_config = match layers.extract() {
+        Ok(config) => config,
+        Err(e) => {
+            warn!("Ignoring configuration file change: {}", e);
+            return;
+        }
+    };
+    if let Some(error) = file_config.validate().first() {
+        warn!("Ignoring configuration file change: {}", error);
+        return;
+    }
+    // The last known configuration has them too, so they never show up as changes.
+    init::debug_overrides(&mut file_config);
+
+    let state = app_handle.state::<RuntimeConfigState>();
+    let mut rtcfg = state.0.lock().await;
+    let changed = rtcfg.__yaml__.diff(&file_config);
+    if changed.is_empty() {
+        return;
+    }
+    let merged = match Config::merge(&rtcfg.__yaml__, &file_config, &rtcfg.yaml) {
+        Ok(merged) => merged,
+        Err(e) => {
+            warn!("Unable to merge configuration file changes: {}", e);
+            return;
+        }
+    };
+    debug!("Configuration file changed: {}", changed.join(", "));
+
+    let previous = std::mem::replace(&mut rtcfg.yaml, merged);
+    rtcfg.__yaml__ = file_config;
+    let (mut applied, mut restart_required) = split_live(changed);
+
+    if applied
+        .iter()
+        .any(|key| key.starts_with("memospot.window."))
+    {
+        apply_window(app_handle, &applied, &rtcfg.yaml.memospot.window);
+    }
+    if applied.iter().any(|key| key.starts_with("memospot.log.")) {
+        apply_logging(&rtcfg);
+    }
+    // The profile is only marked active once the switch succeeds. Until then,
+    // the last known file contents keep the active profile as well, so that
+    // it isn't saved over the user's change on exit.
+    let mut remote_profile = None;
+    if let Some(index) = applied
+        .iter()
+        .position(|key| key == "memospot.remote.default")
+    {
+        let target = rtcfg.yaml.memospot.remote.default.clone();
+        let active = previous.memospot.remote.default.clone();
+        rtcfg.yaml.memospot.remote.default.clone_from(&active);
+        rtcfg.__yaml__.memospot.remote.default = active;
+        match target {
+            Some(name) => remote_profile = Some(name),
+            // Without a default profile, the server depends on other settings.
+            None => restart_required.push(applied.remove(index)),
+        }
+    }
+    drop(rtcfg);
+
+    if let Some(name) = remote_profile {
+        match js_handler::switch_remote_profile(
+            name.clone(),
+            app_handle.clone(),
+            app_handle.state(),
+            app_handle.state(),
+        )
+        .await
+        {
+            Ok(()) => {
+                state.0.lock().await.__yaml__.memospot.remote.default = Some(name);
+            }
+            Err(e) => warn!("Unable to switch to remote profile `{}`: {}", name, e),
+        }
+    }
+
+    info!("Configuration reloaded.");
+    if !restart_required.is_empty() {
+        warn!(
+            "Restart Memospot to apply configuration changes: {}",
+            restart_required.join(", ")
+        );
+    }
+    app_handle
+        .emit_all(
+            RELOADED_EVENT,
+            Reloaded {
+                applied,
+                restart_required,
+            },
+        )
+        .unwrap_or_default();
+}
+
+/// Apply changed window settings to the main window.
+///
+/// Sizes and positions are logical, as when the window is created on startup.
+fn apply_window(app_handle: &AppHandle, changed: &[String], window: &Window) {
+    let Some(main_window) = app_handle.get_window("main") else {
+        return;
+    };
+    let has = |key: &str| {
+        changed
+            .iter()
+            .any(|c| c == &format!("memospot.window.{}", key))
+    };
+
+    if has("fullscreen") {
+        main_window
+            .set_fullscreen(window.fullscreen.unwrap_or_default())
+            .unwrap_or_default();
+    }
+    if has("resizable") {
+        main_window
+            .set_resizable(window.resizable.unwrap_or_default())
+            .unwrap_or_default();
+    }
+    if has("maximized") {
+        if window.maximized.unwrap_or_default() {
+            main_window.maximize().unwrap_or_default();
+        } else {
+            main_window.unmaximize().unwrap_or_default();
+        }
+    }
+    if has("width") || has("height") {
+        if let (Some(width), Some(height)) = (window.width, window.height) {
+            main_window
+                .set_size(LogicalSize::new(width, height))
+                .unwrap_or_default();
+        }
+    }
+    if has("x") || has("y") {
+        if let (Some(x), Some(y)) = (window.x, window.y) {
+            main_window
+                .set_position(LogicalPosition::new(x, y))
+                .unwrap_or_default();
+        }
+    }
+    if has("center") && window.center.unwrap_or_default() {
+        main_window.center().unwrap_or_default();
+    }
+}
+
+/// Apply changed log settings.
+fn apply_logging(rtcfg: &RuntimeConfig) {
+    if let Err(e) = logging::apply(rtcfg) {
+        warn!("Unable to apply log settings: {:#}", e);
+        return;
+    }
+    info!("Log settings applied.");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_live() {
+        let changed = [
+            "memospot.window.width",
+            "memospot.log",
+            "memospot.log.targets.memos",
+            "memospot.remote.default",
+            "memospot.backups.enabled",
+            "memospot.backups.path",
+            "memospot.remote.defaults",
+            "memospot.logging",
+            "memospot",
+            "memos.port",
+        ]
+        .map(str::to_string);
+        let (applied, restart_required) = split_live(Vec::from(changed));
+        assert_eq!(
+            applied,
+            Vec::from([
+                "memospot.window.width",
+                "memospot.log",
+                "memospot.log.targets.memos",
+                "memospot.remote.default",
+                "memospot.backups.enabled",
+            ])
+        );
+        assert_eq!(
+            restart_required,
+            Vec::from([
+                "memospot.backups.path",
+                "memospot.remote.defaults",
+                "memospot.logging",
+                "memospot",
+                "memos.port",
+            ])
+        );
+    }
+}
@@ -0,0 +1,306 @@
+//! Relocate the Memos data directory.
+//!
+//! Memos must be stopped before calling any function in this module,
+//! as the database is copied while no other process is writing to it.
+
+use crate::runtime_config::RuntimeConfig;
+use crate::sqlite;
+use anyhow::{anyhow, bail, Result};
+use homedir::HomeDirExt;
+use log::{debug, info, warn};
+use memospot::absolute_path;
+use migration::assets;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use writable::PathExt;
+
+/// Database files for every Memos mode, including SQLite's temporary files.
+fn database_files(data_dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = Vec::new();
+    for mode in ["prod", "dev", "demo"] {
+        let db_path = data_dir.join(format!("memos_{}.db", mode));
+        for ext in ["db", "db-wal", "db-shm"] {
+            let file = db_path.with_extension(ext);
+            if file.is_file() {
+                files.push(file);
+            }
+        }
+    }
+    files
+}
+
+/// Recursively copy `src` to `dst`, returning every copied (source, target) file pair.
+fn copy_recursive(src: &Path, dst: &Path) -> io::Result<Vec<(PathBuf, PathBuf)>> {
+    let mut copied: Vec<(PathBuf, PathBuf)> = Vec::new();
+    if src.is_file() {
+        fs::copy(src, dst)?;
+        copied.push((src.to_path_buf(), dst.to_path_buf()));
+        return Ok(copied);
+    }
+
+    fs::create_dir_all(dst)?;
+    for entry in fs::read_dir(src)? {
+        let path = entry?.path();
+        let target = dst.join(path.file_name().unwrap_or_default());
+        copied.extend(copy_recursive(&path, &target)?);
+    }
+    Ok(copied)
+}
+
+/// Compare two files byte by byte.
+fn same_contents(a: &Path, b: &Path) -> io::Result<bool> {
+    if fs::metadata(a)?.len() != fs::metadata(b)?.len() {
+        return Ok(false);
+    }
+
+    let mut file_a = File::open(a)?;
+    let mut file_b = File::open(b)?;
+    let mut buf_a = vec![0u8; 64 * 1024];
+    let mut buf_b = vec![0u8; 64 * 1024];
+    loop {
+        let read = file_a.read(&mut buf_a)?;
+        if read == 0 {
+            return Ok(true);
+        }
+        file_b.read_exact(&mut buf_b[..read])?;
+        if buf_a[..read] != buf_b[..read] {
+            return Ok(false);
+        }
+    }
+}
+
+/// Remove a file or directory, logging instead of failing.
+fn remove(path: &Path) {
+    let result = if path.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    };
+    if let Err(e) = result {
+        warn!("Failed to remove `{}`: {}", path.to_string_lossy(), e);
+    }
+}
+
+/// Validate the relocation target, without creating it.
+///
+/// The target must be a writable directory without Memos data, or must be
+/// creatable in a writable directory.
+fn check_target(current: &Path, target: &Path) -> Result<()> {
+    if target == current {
+        bail!("target directory is the current data directory");
+    }
+    if target.starts_with(current.join(assets::ASSETS_DIR)) {
+        bail!("target directory must not be inside the assets directory");
+    }
+    if target.is_file() {
+        bail!("target is a file: {}", target.to_string_lossy());
+    }
+
+    let existing = target.ancestors().find(|ancestor| ancestor.exists());
+    if !existing.is_some_and(|existing| existing.is_dir() && existing.is_writable()) {
+        bail!(
+            "target directory is not writable: {}",
+            target.to_string_lossy()
+        );
+    }
+
+    if !database_files(target).is_empty() || target.join(assets::ASSETS_DIR).exists() {
+        bail!(
+            "target directory already contains Memos data: {}",
+            target.to_string_lossy()
+        );
+    }
+    Ok(())
+}
+
+/// Resolve and validate the relocation target.
+fn resolve_target(rtcfg: &RuntimeConfig, target: &str) -> Result<PathBuf> {
+    let target = target.trim();
+    if target.is_empty() {
+        bail!("target directory is empty");
+    }
+
+    let expanded = Path::new(target).expand_home()?;
+    let target = absolute_path(expanded)?;
+    check_target(&rtcfg.paths.memos_data, &target)?;
+    Ok(target)
+}
+
+/// Relocate Memos data directory.
+///
+/// 1. Checkpoint the database and copy database files and assets to `target`.
+/// 2. Verify that every copied file matches its source.
+/// 3. Rewrite absolute `resource.reference` paths in the copied databases.
+/// 4. Update `memos.data` in the configuration file.
+/// 5. Remove the old copy.
+///
+/// Returns an updated runtime configuration.
+pub async fn memos_data(rtcfg: &RuntimeConfig, target: &str) -> Result<RuntimeConfig> {
+    let current = rtcfg.paths.memos_data.clone();
+    let target = resolve_target(rtcfg, target)?;
+    // Only remove the target on failure if it's created here.
+    let created = !target.exists();
+    fs::create_dir_all(&target)?;
+    let undo = |sources: &[PathBuf]| {
+        if created {
+            remove(&target);
+            return;
+        }
+        for source in sources {
+            remove(&target.join(source.file_name().unwrap_or_default()));
+        }
+    };
+    info!(
+        "Relocating Memos data from `{}` to `{}`.",
+        current.to_string_lossy(),
+        target.to_string_lossy()
+    );
+
+    sqlite::checkpoint(rtcfg).await;
+
+    let mut sources = database_files(&current);
+    let assets_dir = current.join(assets::ASSETS_DIR);
+    if assets_dir.is_dir() {
+        sources.push(assets_dir);
+    }
+
+    // Copy and verify.
+    let mut copied: Vec<(PathBuf, PathBuf)> = Vec::new();
+    let mut result: Result<()> = Ok(());
+    for source in &sources {
+        let destination = target.join(source.file_name().unwrap_or_default());
+        match copy_recursive(source, &destination) {
+            Ok(files) => copied.extend(files),
+            Err(e) => {
+                result = Err(anyhow!(
+                    "failed to copy `{}`: {}",
+                    source.to_string_lossy(),
+                    e
+                ));
+                break;
+            }
+        }
+    }
+    if result.is_ok() {
+        for (source, destination) in &copied {
+            if !same_contents(source, destination).unwrap_or_default() {
+                result = Err(anyhow!(
+                    "copied file does not match its source: {}",
+                    destination.to_string_lossy()
+                ));
+                break;
+            }
+        }
+    }
+    if let Err(e) = result {
+        undo(&sources);
+        return Err(e);
+    }
+    debug!("Copied and verified {} file(s).", copied.len());
+
+    let mut relocated = rtcfg.clone();
+    relocated.paths.memos_data = target.clone();
+    relocated.paths.memos_db_file =
+        target.join(rtcfg.paths.memos_db_file.file_name().unwrap_or_default());
+
+    // Rewrite absolute resource paths on every copied database, then point the
+    // configuration to the new location. Undo the copy if anything goes wrong.
+    let finalize = async {
+        for db_file in database_files(&target)
+            .into_iter()
+            .filter(|f| f.extension().is_some_and(|ext| ext == "db"))
+        {
+            let mut db_rtcfg = relocated.clone();
+            db_rtcfg.paths.memos_db_file = db_file;
+            let db = sqlite::get_database_connection(&db_rtcfg).await?;
+            let rewritten = assets::relativize_references(&db, &current).await;
+            let _ = db.close().await;
+            rewritten?;
+            sqlite::checkpoint(&db_rtcfg).await;
+        }
+
+        relocated.yaml.memos.data = Some(target.to_string_lossy().to_string());
+        relocated
+            .paths
+            .memospot_config_layers
+            .save(&relocated.yaml)?;
+        relocated.__yaml__ = relocated.yaml.clone();
+        Ok::<(), anyhow::Error>(())
+    };
+    if let Err(e) = finalize.await {
+        undo(&sources);
+        return Err(e);
+    }
+
+    for source in &sources {
+        remove(source);
+    }
+
+    info!(
+        "Memos data relocated to `{}`.",
+        relocated.paths.memos_data.to_string_lossy()
+    );
+    Ok(relocated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_target() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let current = tmp_dir.path().join("current");
+        fs::create_dir_all(current.join(assets::ASSETS_DIR)).unwrap();
+
+        // A new directory is valid, and isn't created.
+        let new_target = tmp_dir.path().join("new/memos");
+        assert!(check_target(&current, &new_target).is_ok());
+        assert!(!new_target.exists());
+
+        assert!(check_target(&current, &current).is_err());
+        assert!(check_target(&current, &current.join("assets/inner")).is_err());
+
+        let file = tmp_dir.path().join("file");
+        fs::write(&file, "").unwrap();
+        assert!(check_target(&current, &file).is_err());
+        assert!(check_target(&current, &file.join("inner")).is_err());
+
+        let used = tmp_dir.path().join("used");
+        fs::create_dir_all(&used).unwrap();
+        fs::write(used.join("memos_prod.db"), "").unwrap();
+        assert!(check_target(&current, &used).is_err());
+    }
+
+    #[test]
+    fn test_copy_and_verify() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let source = tmp_dir.path().join("source");
+        fs::create_dir_all(source.join("assets/2024")).unwrap();
+        fs::write(source.join("memos_prod.db"), "db").unwrap();
+        fs::write(source.join("memos_prod.db-wal"), "wal").unwrap();
+        fs::write(source.join("assets/2024/a.png"), "png").unwrap();
+        assert_eq!(
+            database_files(&source),
+            vec![
+                source.join("memos_prod.db"),
+                source.join("memos_prod.db-wal")
+            ]
+        );
+
+        let target = tmp_dir.path().join("target");
+        let copied = copy_recursive(&source, &target).unwrap();
+        assert_eq!(copied.len(), 3);
+        for (from, to) in &copied {
+            assert!(same_contents(from, to).unwrap());
+        }
+
+        fs::write(target.join("assets/2024/a.png"), "gif").unwrap();
+        assert!(!same_contents(
+            &source.join("assets/2024/a.png"),
+            &target.join("assets/2024/a.png")
+        )
+        .unwrap());
+    }
+}
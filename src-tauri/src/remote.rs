@@ -0,0 +1,239 @@
+//! Remote Memos server helpers.
+
+use crate::network;
+use crate::secrets;
+use anyhow::{anyhow, bail, Result};
+use config::{Proxy, RemoteServer, RemoteTls, SecretRef};
+use homedir::HomeDirExt;
+use log::debug;
+use memospot::absolute_path;
+use sha2::{Digest, Sha256};
+use std::error::Error;
+use std::path::Path;
+use std::time::Duration;
+
+/// How long to wait for a remote server to respond.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Cookie read by Memos to authenticate requests.
+const ACCESS_TOKEN_COOKIE: &str = "memos.access-token";
+
+/// Whether custom TLS settings are in use.
+pub fn has_custom_tls(tls: &RemoteTls) -> bool {
+    ca_bundle_path(tls).is_some() || pinned_fingerprint(tls).is_some()
+}
+
+/// Configured CA bundle path, if any.
+fn ca_bundle_path(tls: &RemoteTls) -> Option<&str> {
+    tls.ca_bundle
+        .as_deref()
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+}
+
+/// Configured certificate fingerprint, normalized to lowercase hex without separators.
+fn pinned_fingerprint(tls: &RemoteTls) -> Option<String> {
+    let fingerprint: String = tls
+        .fingerprint
+        .as_deref()?
+        .chars()
+        .filter(|c| c.is_ascii_hexdigit())
+        .collect::<String>()
+        .to_lowercase();
+    (!fingerprint.is_empty()).then_some(fingerprint)
+}
+
+/// SHA-256 digest of a DER-encoded certificate, as lowercase hex.
+fn fingerprint(der: &[u8]) -> String {
+    Sha256::digest(der)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// HTTP client for a remote server, honoring its TLS and proxy settings.
+///
+/// With a pinned fingerprint, the certificate chain is not verified by the
+/// underlying client. Instead, the server certificate of every response is
+/// compared to the fingerprint, and mismatching responses are turned into errors.
+pub struct Client {
+    inner: reqwest::Client,
+    fingerprint: Option<String>,
+}
+
+impl Client {
+    /// Build a client for the given TLS and proxy settings.
+    pub fn new(tls: &RemoteTls, proxy: &Proxy) -> Result<Self> {
+        let mut builder = network::client_builder(proxy)?.timeout(CHECK_TIMEOUT);
+
+        if let Some(ca_bundle) = ca_bundle_path(tls) {
+            let path = absolute_path(Path::new(ca_bundle).expand_home()?)?;
+            let pem = std::fs::read(&path).map_err(|e| {
+                anyhow!(
+                    "unable to read CA bundle `{}`: {}",
+                    path.to_string_lossy(),
+                    e
+                )
+            })?;
+            let certificates = reqwest::Certificate::from_pem_bundle(&pem).map_err(|e| {
+                anyhow!("invalid CA bundle `{}`: {}", path.to_string_lossy(), e)
+            })?;
+            debug!(
+                "Loaded {} certificate(s) from `{}`.",
+                certificates.len(),
+                path.to_string_lossy()
+            );
+            for certificate in certificates {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+
+        let fingerprint = pinned_fingerprint(tls);
+        if fingerprint.is_some() {
+            builder = builder.danger_accept_invalid_certs(true).tls_info(true);
+        }
+
+        Ok(Self {
+            inner: builder.build()?,
+            fingerprint,
+        })
+    }
+
+    /// Send a GET request, checking the pinned fingerprint if any.
+    pub async fn get(&self, url: &str) -> Result<reqwest::Response> {
+        let response = self.inner.get(url).send().await.map_err(describe)?;
+        let Some(expected) = &self.fingerprint else {
+            return Ok(response);
+        };
+
+        let actual = response
+            .extensions()
+            .get::<reqwest::tls::TlsInfo>()
+            .and_then(|info| info.peer_certificate())
+            .map(fingerprint);
+        match actual {
+            Some(actual) if &actual == expected => {
+                debug!("Server certificate matches the pinned fingerprint.");
+                Ok(response)
+            }
+            Some(actual) => {
+                bail!(
+                    "TLS certificate fingerprint mismatch.\nExpected: {}\nFound: {}",
+                    expected,
+                    actual
+                );
+            }
+            None => {
+                bail!("unable to verify the pinned fingerprint: server did not present a TLS certificate");
+            }
+        }
+    }
+}
+
+/// Check whether a Memos server is reachable.
+///
+/// Queries the `/healthz` endpoint, the same one used by the loader page.
+pub async fn check(memos_url: &str, tls: &RemoteTls, proxy: &Proxy) -> Result<()> {
+    let endpoint = format!("{}/healthz", memos_url.trim_end_matches('/'));
+    debug!("Checking remote server at {}", endpoint);
+
+    let response = Client::new(tls, proxy)?.get(&endpoint).await?;
+    if !response.status().is_success() {
+        bail!("server responded `{}`", response.status());
+    }
+    Ok(())
+}
+
+/// Turn a request error into a short, user-facing reason.
+fn describe(error: reqwest::Error) -> anyhow::Error {
+    if error.is_timeout() {
+        return anyhow!("no response after {:?}", CHECK_TIMEOUT);
+    }
+
+    // Certificate errors are nested deep into the error chain.
+    let mut reasons: Vec<String> = Vec::from([error.to_string()]);
+    let mut source = error.source();
+    while let Some(e) = source {
+        reasons.push(e.to_string());
+        source = e.source();
+    }
+    let reason = reasons.join(": ");
+    if reason.to_lowercase().contains("certificate") {
+        return anyhow!(
+            "TLS certificate verification failed: {}\n\nSet `ca_bundle` or `fingerprint` in the remote server `tls` settings to trust this server.",
+            reason
+        );
+    }
+
+    if error.is_connect() {
+        return anyhow!("unable to connect: {}", reason);
+    }
+    anyhow!(reason)
+}
+
+/// Name of the secret holding the access token of a remote profile.
+///
+/// The unnamed `remote.url` server uses "remote.default".
+fn token_secret(profile: Option<&str>) -> String {
+    format!("remote.{}", profile.unwrap_or("default"))
+}
+
+/// Move a plain-text token into the system keyring, replacing it with a secret reference.
+///
+/// References are left as they are. Returns true if the token was moved.
+fn store_token(token: &mut Option<String>, profile: Option<&str>) -> Result<bool> {
+    let Some(value) = token.as_deref().map(str::trim) else {
+        return Ok(false);
+    };
+    if value.is_empty() || SecretRef::parse(value).is_some() {
+        return Ok(false);
+    }
+
+    let name = token_secret(profile);
+    secrets::store(&name, value)?;
+    *token = Some(SecretRef::Store(name).to_string());
+    Ok(true)
+}
+
+/// Move plain-text access tokens from the configuration to the system keyring.
+///
+/// Returns true if the configuration changed and must be saved.
+pub fn store_access_tokens(remote: &mut RemoteServer) -> Result<bool> {
+    let mut changed = store_token(&mut remote.access_token, None)?;
+    for profile in remote.profiles.iter_mut().flatten() {
+        changed |= store_token(&mut profile.access_token, Some(&profile.name))?;
+    }
+    Ok(changed)
+}
+
+/// Get the access token for the active remote server.
+///
+/// Only secret references are resolved: plain-text tokens are moved to the
+/// system keyring on startup, and left unused if that fails.
+pub fn access_token(remote: &RemoteServer) -> Option<String> {
+    let token = match remote.active_profile() {
+        Some(profile) => &profile.access_token,
+        None => &remote.access_token,
+    };
+    token.as_deref().and_then(SecretRef::parse)?;
+    secrets::resolve_optional(token)
+}
+
+/// Script that seeds the Memos session with an access token.
+///
+/// Runs once per session: the page is reloaded after setting the
+/// cookie, so the first requests are already authenticated.
+pub fn session_script(token: &str) -> String {
+    let cookie = format!(
+        "{}={}; path=/; max-age=31536000; SameSite=Lax",
+        ACCESS_TOKEN_COOKIE, token
+    );
+    format!(
+        r#"if (!sessionStorage.getItem("memospot.session")) {{
+    sessionStorage.setItem("memospot.session", "seeded");
+    document.cookie = {} + (location.protocol === "https:" ? "; Secure" : "");
+    location.reload();
+}}"#,
+        serde_json::to_string(&cookie).unwrap_or_default()
+    )
+}
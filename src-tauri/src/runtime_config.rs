@@ -1,4 +1,5 @@
-use config::Config;
+use config::{Config, ConfigLayers};
+use memospot::display_url;
 
 use std::path::PathBuf;
 
@@ -23,6 +24,10 @@ pub struct RuntimeConfigPaths {
     pub memospot_bin: PathBuf,
     /// Memospot configuration file path.
     pub memospot_config_file: PathBuf,
+    /// Memospot configuration files, including system and policy layers.
+    ///
+    /// The user layer is `memospot_config_file`.
+    pub memospot_config_layers: ConfigLayers,
     /// Memospot current working directory path.
     pub memospot_cwd: PathBuf,
     /// Memospot data directory path.
@@ -43,14 +48,50 @@ pub struct RuntimeConfig {
     /// Whether Memospot is managing a local Memos server.
     pub managed_server: bool,
 
+    /// Whether Memospot fell back to the local Memos server
+    /// because the remote server was unreachable.
+    pub offline_fallback: bool,
+
     /// Store current YAML config. May be modified during app runtime.
     ///
     /// This is the main configuration object used throughout the app.
     pub yaml: Config,
 
-    /// Store initial YAML to compare with current YAML and save the file
-    /// if configuration changed.
+    /// Store the last known contents of the configuration file, to compare
+    /// with current YAML and save the file if configuration changed.
     ///
-    /// DO NOT modify this field after app startup.
+    /// Only update this field after reading or writing the configuration file.
     pub __yaml__: Config,
 }
+
+impl RuntimeConfig {
+    /// Whether the configuration selects the local Memos server,
+    /// which Memospot starts and stops on its own.
+    ///
+    /// Used to set `managed_server` whenever the active remote profile changes.
+    pub fn selects_managed_server(&self) -> bool {
+        self.yaml.memospot.remote.active_url().is_none()
+    }
+
+    /// Main window title.
+    ///
+    /// Shows the remote server address or the active workspace, if any.
+    pub fn window_title(&self, app_version: &str) -> String {
+        if self.offline_fallback {
+            return format!("Memospot {} - offline (local server)", app_version);
+        }
+        if !self.managed_server {
+            return format!(
+                "Memospot {} - {}",
+                app_version,
+                display_url(&self.memos_url)
+            );
+        }
+        match &self.yaml.memospot.workspaces.active {
+            Some(workspace) if !workspace.is_empty() => {
+                format!("Memospot {} - {}", app_version, workspace)
+            }
+            _ => format!("Memospot {}", app_version),
+        }
+    }
+}
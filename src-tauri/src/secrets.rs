@@ -0,0 +1,81 @@
+//! Resolution of secret references in the configuration.
+//!
+//! See [`config::SecretRef`] for the supported references. This module is the
+//! only one accessing the system keyring: secrets saved by Memospot itself,
+//! such as remote access tokens, are named secrets too.
+
+use anyhow::{bail, Context, Result};
+use config::{Config, SecretRef};
+use homedir::HomeDirExt;
+use log::warn;
+use memospot::absolute_path;
+use std::fs;
+
+/// Service name used for system keyring entries.
+const KEYRING_SERVICE: &str = "memospot";
+
+/// System keyring entry for a named secret.
+fn keyring_entry(name: &str) -> Result<keyring::Entry> {
+    Ok(keyring::Entry::new(
+        KEYRING_SERVICE,
+        &format!("secret:{}", name),
+    )?)
+}
+
+/// Read the secret a reference points to.
+fn read(secret: &SecretRef) -> Result<String> {
+    match secret {
+        _ if secret.is_empty() => bail!("empty secret reference"),
+        SecretRef::Store(name) => keyring_entry(name)?.get_password().with_context(|| {
+            format!("unable to read secret `{}` from the system keyring", name)
+        }),
+        SecretRef::File(path) => {
+            let expanded = path.expand_home().unwrap_or_else(|_| path.clone());
+            let path = absolute_path(&expanded).unwrap_or(expanded);
+            let contents = fs::read_to_string(&path).with_context(|| {
+                format!("unable to read secret file `{}`", path.to_string_lossy())
+            })?;
+            Ok(contents.trim_end_matches(['\r', '\n']).to_string())
+        }
+    }
+}
+
+/// Resolve a configuration value. Plain values are returned as is.
+pub fn resolve(value: &str) -> Result<String> {
+    match SecretRef::parse(value) {
+        Some(secret) => read(&secret),
+        None => Ok(value.to_string()),
+    }
+}
+
+/// Resolve an optional configuration value, logging failures.
+pub fn resolve_optional(value: &Option<String>) -> Option<String> {
+    let value = value.as_deref()?;
+    match resolve(value) {
+        Ok(resolved) => Some(resolved),
+        Err(e) => {
+            warn!("{:#}", e);
+            None
+        }
+    }
+}
+
+/// Save a named secret in the system keyring, for use as `secret:NAME`.
+pub fn store(name: &str, value: &str) -> Result<()> {
+    if name.trim().is_empty() {
+        bail!("secret name must not be empty");
+    }
+    keyring_entry(name.trim())?.set_password(value)?;
+    Ok(())
+}
+
+/// Check that every secret reference in the configuration can be resolved.
+///
+/// Returns a description of each failure.
+pub fn check(config: &Config) -> Vec<String> {
+    config
+        .secret_refs()
+        .into_iter()
+        .filter_map(|(key, secret)| read(&secret).err().map(|e| format!("`{}`: {:#}", key, e)))
+        .collect()
+}
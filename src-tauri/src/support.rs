@@ -0,0 +1,303 @@
+//! Diagnostic support bundles.
+//!
+//! A support bundle gathers what's needed to troubleshoot an installation into
+//! a single zip file: the redacted configuration, recent logs and Memos output,
+//! paths, versions and database statistics.
+//!
+//! Note contents are never included: only sizes and row counts are read
+//! from the Memos database. Known secret values, such as the proxy password
+//! and sensitive Memos environment variables, are masked in the logs.
+
+use crate::logging::{self, LogEntry};
+use crate::memos::{self, MEMOS_LOG_TARGET};
+use crate::runtime_config::RuntimeConfig;
+use crate::{init, network, secrets, sqlite, webview, zip};
+use anyhow::{Context, Result};
+use config::secret::REDACTED;
+use log::info;
+use migration::{Migrator, MigratorTrait};
+use sea_orm::{ConnectionTrait, DatabaseConnection, DbBackend, Statement};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::env::consts;
+use std::fs;
+use std::path::Path;
+
+/// Number of log files included: the current one and the newest rotated ones.
+const LOG_FILES: usize = 2;
+
+/// Shortest secret value masked in the logs. Shorter ones would mask unrelated text.
+const MIN_SECRET_LEN: usize = 4;
+
+/// Number of Memos output records included.
+const MEMOS_OUTPUT_RECORDS: usize = 1000;
+
+/// List the database tables, leaving out SQLite's own.
+const TABLES_QUERY: &str =
+    "SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%' ORDER BY name;";
+
+/// Versions and platform information.
+#[derive(Debug, Serialize)]
+struct SystemInfo {
+    created: String,
+    memospot_version: String,
+    /// None if the server couldn't be reached.
+    memos_version: Option<String>,
+    memos_url: String,
+    managed_server: bool,
+    offline_fallback: bool,
+    os: &'static str,
+    os_family: &'static str,
+    arch: &'static str,
+    webview_available: bool,
+}
+
+/// A row of the `memospot_migrations` table.
+#[derive(Debug, Serialize)]
+struct MigrationRow {
+    version: String,
+    applied_at: i64,
+}
+
+/// Database statistics. Sizes and row counts only, never contents.
+#[derive(Debug, Serialize)]
+struct DatabaseStats {
+    /// Database file size, in bytes.
+    size: u64,
+    /// Write-ahead log size, in bytes.
+    wal_size: u64,
+    page_size: i64,
+    page_count: i64,
+    freelist_count: i64,
+    /// Row count of each table.
+    tables: BTreeMap<String, i64>,
+    migrations: Vec<MigrationRow>,
+    pending_migrations: Vec<String>,
+}
+
+/// Default support bundle file name, with the current date and time.
+pub fn file_name() -> String {
+    let datetime = chrono::Local::now().format("%Y%m%d-%H%M%S");
+    format!("memospot-support-{}.zip", datetime)
+}
+
+/// Read a single integer, such as a PRAGMA value or a row count.
+async fn query_i64(db: &DatabaseConnection, sql: &str) -> Result<i64> {
+    let row = db
+        .query_one(Statement::from_string(DbBackend::Sqlite, sql))
+        .await?
+        .with_context(|| format!("no result for `{}`", sql))?;
+    Ok(row.try_get_by_index::<i64>(0)?)
+}
+
+/// Gather database statistics.
+async fn database_stats(rtcfg: &RuntimeConfig) -> Result<DatabaseStats> {
+    let db_file = &rtcfg.paths.memos_db_file;
+    let wal_file = db_file.with_extension("db-wal");
+    let size = fs::metadata(db_file)
+        .with_context(|| format!("unable to read `{}`", db_file.to_string_lossy()))?
+        .len();
+
+    let db = sqlite::get_database_connection(rtcfg).await?;
+    let mut tables: BTreeMap<String, i64> = BTreeMap::new();
+    let table_names = db
+        .query_all(Statement::from_string(DbBackend::Sqlite, TABLES_QUERY))
+        .await?;
+    for row in table_names {
+        let name = row.try_get_by_index::<String>(0)?;
+        let sql = format!("SELECT COUNT(*) FROM \"{}\";", name.replace('"', "\"\""));
+        tables.insert(name, query_i64(&db, &sql).await?);
+    }
+
+    let migrations = Migrator::get_migration_models(&db)
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .map(|model| MigrationRow {
+            version: model.version,
+            applied_at: model.applied_at,
+        })
+        .collect();
+    let pending_migrations = Migrator::get_pending_migrations(&db)
+        .await
+        .unwrap_or_default()
+        .iter()
+        .map(|migration| migration.name().to_string())
+        .collect();
+
+    let stats = DatabaseStats {
+        size,
+        wal_size: fs::metadata(&wal_file).map(|m| m.len()).unwrap_or_default(),
+        page_size: query_i64(&db, "PRAGMA page_size;").await?,
+        page_count: query_i64(&db, "PRAGMA page_count;").await?,
+        freelist_count: query_i64(&db, "PRAGMA freelist_count;").await?,
+        tables,
+        migrations,
+        pending_migrations,
+    };
+    let _ = db.close().await;
+    Ok(stats)
+}
+
+/// Secret values that may show up in the logs, longest first.
+fn secret_values(rtcfg: &RuntimeConfig) -> Vec<String> {
+    let proxy = &rtcfg.yaml.memospot.network.proxy;
+    let mut values = memos::secret_values(rtcfg);
+    values.extend(secrets::resolve_optional(&proxy.password));
+    values.extend(network::url_password(proxy));
+    values.retain(|value| value.len() >= MIN_SECRET_LEN);
+    // Longer values first, so a secret containing another is masked whole.
+    values.sort_by(|a, b| b.len().cmp(&a.len()).then(a.cmp(b)));
+    values.dedup();
+    values
+}
+
+/// Mask secret values in log contents.
+fn redact(contents: &str, secrets: &[String]) -> String {
+    secrets
+        .iter()
+        .fold(contents.to_string(), |contents, secret| {
+            contents.replace(secret.as_str(), REDACTED)
+        })
+}
+
+/// Write a log record back as a text line.
+fn format_entry(entry: &LogEntry) -> String {
+    format!(
+        "{} - {} [{}]: {}\n",
+        entry.timestamp, entry.level, entry.target, entry.message
+    )
+}
+
+/// Create a support bundle at `output`.
+///
+/// Parts that can't be gathered, such as database statistics without a
+/// database, are listed in the bundle's `README.txt` instead.
+pub async fn create(rtcfg: &RuntimeConfig, app_version: &str, output: &Path) -> Result<()> {
+    let mut entries: Vec<(String, Vec<u8>)> = Vec::new();
+    let mut notes: Vec<String> = Vec::new();
+
+    let system = SystemInfo {
+        created: chrono::Local::now().to_rfc3339(),
+        memospot_version: app_version.to_string(),
+        memos_version: memos::version(rtcfg).await,
+        memos_url: rtcfg.memos_url.clone(),
+        managed_server: rtcfg.managed_server,
+        offline_fallback: rtcfg.offline_fallback,
+        os: consts::OS,
+        os_family: consts::FAMILY,
+        arch: consts::ARCH,
+        webview_available: webview::is_available(),
+    };
+    if system.memos_version.is_none() {
+        notes.push("The Memos server didn't report its version.".to_string());
+    }
+    entries.push((
+        "system.json".to_string(),
+        serde_json::to_vec_pretty(&system)?,
+    ));
+    entries.push((
+        "paths.txt".to_string(),
+        format!("{:#?}\n", rtcfg.paths).into_bytes(),
+    ));
+
+    // Secrets are redacted from both the configuration and its origins.
+    entries.push((
+        "config.yaml".to_string(),
+        serde_yaml::to_string(&rtcfg.yaml.redacted())?.into_bytes(),
+    ));
+    match init::config_origins(&rtcfg.paths.memospot_config_layers) {
+        Ok(origins) => {
+            let origins = rtcfg.yaml.override_origins(origins, "runtime");
+            entries.push((
+                "config-origins.json".to_string(),
+                serde_json::to_vec_pretty(&origins)?,
+            ));
+        }
+        Err(e) => notes.push(format!("Unable to read the configuration layers: {}", e)),
+    }
+
+    if !rtcfg.managed_server {
+        notes.push("Database statistics are only gathered for the local server.".to_string());
+    } else if !rtcfg.paths.memos_db_file.exists() {
+        notes.push("The Memos database doesn't exist yet.".to_string());
+    } else {
+        match database_stats(rtcfg).await {
+            Ok(stats) => entries.push((
+                "database.json".to_string(),
+                serde_json::to_vec_pretty(&stats)?,
+            )),
+            Err(e) => notes.push(format!("Unable to gather database statistics: {:#}", e)),
+        }
+    }
+
+    let log = &rtcfg.yaml.memospot.log;
+    if !log.enabled.unwrap_or_default() {
+        notes.push("Logging is disabled, so no logs are included.".to_string());
+    }
+    let log_files = logging::files(log, &rtcfg.paths.memospot_data);
+    let secrets = secret_values(rtcfg);
+    let mut memos_output: Vec<LogEntry> = Vec::new();
+    // Newest files come first, so records are gathered in reverse.
+    for (file, path) in log_files.iter().take(LOG_FILES) {
+        // Rotated files are decompressed, to be redacted.
+        let contents = match logging::read(path) {
+            Ok(contents) => redact(&contents, &secrets),
+            Err(e) => {
+                notes.push(format!("Unable to read `{}`: {:#}", file.name, e));
+                continue;
+            }
+        };
+        let name = file.name.trim_end_matches(".gz");
+        entries.push((format!("logs/{}", name), contents.as_bytes().to_vec()));
+        if memos_output.len() >= MEMOS_OUTPUT_RECORDS {
+            continue;
+        }
+        let mut records: Vec<LogEntry> = logging::parse(&contents)
+            .into_iter()
+            .filter(|entry| logging::matches(entry, None, Some(MEMOS_LOG_TARGET)))
+            .collect();
+        records.append(&mut memos_output);
+        memos_output = records;
+    }
+    if !memos_output.is_empty() {
+        let skip = memos_output.len().saturating_sub(MEMOS_OUTPUT_RECORDS);
+        let output: String = memos_output[skip..].iter().map(format_entry).collect();
+        entries.push(("memos-output.log".to_string(), output.into_bytes()));
+    }
+
+    let mut readme = String::from(
+        "Memospot support bundle.\n\n\
+        Secrets are redacted, and note contents are never included.\n",
+    );
+    if !notes.is_empty() {
+        readme.push('\n');
+        for note in &notes {
+            readme.push_str(&format!("- {}\n", note));
+        }
+    }
+    entries.insert(0, ("README.txt".to_string(), readme.into_bytes()));
+
+    zip::contents(&entries, output).await?;
+    info!(
+        bundle:% = output.display();
+        "Support bundle created: {}",
+        output.to_string_lossy()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact() {
+        let secrets = Vec::from(["hunter2-long".to_string(), "hunter2".to_string()]);
+        assert_eq!(
+            redact("token=hunter2-long, password=hunter2\n", &secrets),
+            "token=<redacted>, password=<redacted>\n"
+        );
+        assert_eq!(redact("nothing to hide", &secrets), "nothing to hide");
+    }
+}
@@ -0,0 +1,98 @@
+//! Update checks through the configured proxy.
+//!
+//! Tauri's built-in updater uses its own HTTP client, which can only reach a proxy
+//! through process-wide environment variables. When a proxy is configured, the
+//! built-in updater is turned off, and this module checks for updates instead,
+//! offering to open the release page.
+
+use crate::network;
+use anyhow::{anyhow, Result};
+use config::Proxy;
+use log::{debug, info, warn};
+use memospot::confirm_dialog;
+use native_dialog::MessageType;
+use serde::Deserialize;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// Where new releases can be downloaded.
+const RELEASES_PAGE: &str = "https://github.com/memospot/memospot/releases/latest";
+
+/// How long to wait for an update endpoint to respond.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The part of the update manifest used here.
+#[derive(Debug, Deserialize)]
+struct Release {
+    version: String,
+}
+
+/// Latest released version, from the first update endpoint that answers.
+async fn latest_version(endpoints: &[String], proxy: &Proxy) -> Result<semver::Version> {
+    let client = network::client_builder(proxy)?.timeout(TIMEOUT).build()?;
+    let mut last_error = anyhow!("no update endpoints configured");
+    for endpoint in endpoints {
+        let response = client
+            .get(endpoint)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+        match response {
+            Ok(response) => {
+                let release: Release = serde_json::from_str(&response.text().await?)?;
+                return Ok(semver::Version::parse(
+                    release.version.trim_start_matches('v'),
+                )?);
+            }
+            Err(e) => last_error = e.into(),
+        }
+    }
+    Err(last_error)
+}
+
+/// Check for updates through the configured proxy.
+///
+/// If a newer version is available, ask whether to open the release page.
+pub async fn check(app_handle: AppHandle, proxy: Proxy) {
+    let endpoints: Vec<String> = app_handle
+        .config()
+        .tauri
+        .updater
+        .endpoints
+        .iter()
+        .flatten()
+        .map(|endpoint| endpoint.to_string())
+        .collect();
+    let Ok(current) = semver::Version::parse(&app_handle.package_info().version.to_string())
+    else {
+        return;
+    };
+
+    let latest = match latest_version(&endpoints, &proxy).await {
+        Ok(latest) => latest,
+        Err(e) => {
+            warn!("Failed to check for updates: {}", e);
+            return;
+        }
+    };
+    if latest <= current {
+        debug!("Memospot is up to date.");
+        return;
+    }
+
+    info!("Memospot {} is available.", latest);
+    let user_confirmed = confirm_dialog(
+        "Update Available",
+        &format!(
+            "Memospot {} is available. You have {}.\n\nDo you want to open the download page?",
+            latest, current
+        ),
+        MessageType::Info,
+    );
+    if !user_confirmed {
+        return;
+    }
+    if let Err(e) = tauri::api::shell::open(&app_handle.shell_scope(), RELEASES_PAGE, None) {
+        warn!("Failed to open the download page: {}", e);
+    }
+}
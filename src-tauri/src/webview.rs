@@ -1,9 +1,11 @@
+use config::Proxy;
 use std::env::consts;
 use std::io::{Error, ErrorKind, Result};
 use std::process::Command;
 
 #[cfg(windows)]
 use {
+    crate::network,
     std::io::{BufWriter, Cursor, Write},
     tempfile::TempDir,
     winreg::enums::{HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE},
@@ -47,11 +49,12 @@ pub fn open_install_website() -> Result<()> {
 }
 
 #[cfg(windows)]
-pub async fn install() -> Result<()> {
+pub async fn install(proxy: &Proxy) -> Result<()> {
     const WEBVIEW2_BOOTSTRAPPER_URL: &str = "https://go.microsoft.com/fwlink/p/?LinkId=2124703";
     const DEFAULT_FILENAME: &str = "MicrosoftEdgeWebview2Setup.exe";
 
-    let client = reqwest::Client::builder()
+    let client = network::client_builder(proxy)
+        .map_err(|e| Error::new(ErrorKind::Other, e))?
         .user_agent("Tauri")
         .gzip(true)
         .build()
@@ -135,7 +138,7 @@ pub async fn install() -> Result<()> {
 }
 
 #[cfg(not(windows))]
-pub async fn install() -> Result<()> {
+pub async fn install(_proxy: &Proxy) -> Result<()> {
     Err(Error::new(
         ErrorKind::Other,
         "unable to auto-install WebView on this system.",
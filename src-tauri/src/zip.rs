@@ -48,6 +48,25 @@ pub async fn related_files(
     Ok(())
 }
 
+/// Create a zip file from in-memory entries, given as file names and contents.
+///
+/// Entries are compressed with Deflate, which the zip tools built into
+/// operating systems can read.
+pub async fn contents(entries: &[(String, Vec<u8>)], output_zip: &Path) -> Result<()> {
+    debug!("Output zip file: {}", output_zip.to_string_lossy());
+
+    let file = File::create(output_zip).await?;
+    let mut writer: TokioZipFileWriter<File> = ZipFileWriter::with_tokio(file);
+    for (filename, data) in entries {
+        debug!("Adding file to zip: {}", filename);
+        let builder = ZipEntryBuilder::new(filename.clone().into(), Compression::Deflate);
+        writer.write_entry_whole(builder, data).await?;
+    }
+    writer.close().await?;
+
+    Ok(())
+}
+
 /// Write a file to a zip writer.
 async fn write_entry(input_path: &Path, writer: &mut TokioZipFileWriter<File>) -> Result<()> {
     let mut input_file = File::open(input_path).await?;
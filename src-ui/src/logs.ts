@@ -0,0 +1,135 @@
+import { type LogEntry, createSupportBundle, listLogFiles, tailLog } from "./tauri";
+
+const FOLLOW_INTERVAL = 3000;
+
+function element<T extends HTMLElement>(id: string): T {
+    return document.getElementById(id) as T;
+}
+
+function formatSize(bytes: number): string {
+    if (bytes < 1024) {
+        return `${bytes} B`;
+    }
+    if (bytes < 1024 * 1024) {
+        return `${(bytes / 1024).toFixed(1)} KB`;
+    }
+    return `${(bytes / 1024 / 1024).toFixed(1)} MB`;
+}
+
+async function loadFiles() {
+    const logFiles = await listLogFiles();
+    const fileSelect = element<HTMLSelectElement>("file");
+    const selected = fileSelect.value;
+    fileSelect.replaceChildren(
+        ...logFiles.files.map((file) => {
+            const option = document.createElement("option");
+            option.value = file.name;
+            option.textContent = `${file.name} (${formatSize(file.size)})`;
+            return option;
+        })
+    );
+    if (logFiles.files.some((file) => file.name === selected)) {
+        fileSelect.value = selected;
+    }
+
+    const notice = element<HTMLParagraphElement>("notice");
+    if (!logFiles.enabled) {
+        notice.textContent =
+            'Logging is disabled. Set "memospot.log.enabled" to true in memospot.yaml ' +
+            `to write ${logFiles.path}.`;
+    } else if (logFiles.files.length === 0) {
+        notice.textContent = `Nothing has been logged to ${logFiles.path} yet.`;
+    } else {
+        notice.textContent = `Log file: ${logFiles.path}`;
+    }
+}
+
+function entryRow(entry: LogEntry): HTMLTableRowElement {
+    const row = document.createElement("tr");
+    const fields = Object.entries(entry.fields)
+        .map(([key, value]) => {
+            return `${key}=${typeof value === "string" ? value : JSON.stringify(value)}`;
+        })
+        .join(" ");
+    const cells: [string, string][] = [
+        ["timestamp", entry.timestamp],
+        [`level level-${entry.level}`, entry.level],
+        ["target", entry.target],
+        ["message", entry.message]
+    ];
+    for (const [className, text] of cells) {
+        const cell = document.createElement("td");
+        cell.className = className;
+        cell.textContent = text;
+        row.appendChild(cell);
+    }
+    if (fields) {
+        const span = document.createElement("span");
+        span.className = "fields";
+        span.textContent = ` ${fields}`;
+        row.lastElementChild?.appendChild(span);
+    }
+    return row;
+}
+
+async function loadEntries() {
+    const file = element<HTMLSelectElement>("file").value || null;
+    const level = element<HTMLSelectElement>("level").value || null;
+    const target = element<HTMLInputElement>("target").value.trim() || null;
+    const lines = Number(element<HTMLSelectElement>("lines").value);
+
+    const entries = await tailLog(file, lines, level, target);
+    const body = element<HTMLTableSectionElement>("entries");
+    const atBottom = window.innerHeight + window.scrollY >= document.body.scrollHeight - 8;
+    body.replaceChildren(...entries.map(entryRow));
+    if (atBottom) {
+        window.scrollTo(0, document.body.scrollHeight);
+    }
+}
+
+async function supportBundle() {
+    const button = element<HTMLButtonElement>("support-bundle");
+    const notice = element<HTMLParagraphElement>("support-notice");
+    button.disabled = true;
+    try {
+        const path = await createSupportBundle();
+        notice.textContent = `Support bundle saved to ${path}`;
+    } catch (error) {
+        notice.textContent = `Unable to create the support bundle: ${error}`;
+    } finally {
+        button.disabled = false;
+    }
+}
+
+async function refresh() {
+    try {
+        await loadFiles();
+        await loadEntries();
+    } catch (error) {
+        const notice = element<HTMLParagraphElement>("notice");
+        notice.textContent = `Unable to read the log: ${error}`;
+    }
+}
+
+document.addEventListener("DOMContentLoaded", () => {
+    const form = element<HTMLFormElement>("filters");
+    form.addEventListener("submit", (event) => {
+        event.preventDefault();
+        refresh();
+    });
+    element("support-bundle").addEventListener("click", supportBundle);
+    for (const id of ["file", "level", "lines"]) {
+        element(id).addEventListener("change", refresh);
+    }
+
+    // Rotated files don't change, so only the current file is followed.
+    setInterval(() => {
+        const follow = element<HTMLInputElement>("follow").checked;
+        const fileSelect = element<HTMLSelectElement>("file");
+        if (follow && fileSelect.selectedIndex <= 0) {
+            refresh();
+        }
+    }, FOLLOW_INTERVAL);
+
+    refresh();
+});
@@ -1,11 +1,14 @@
 import { ResponseType, fetch } from "@tauri-apps/api/http";
 import { LogoBlinker } from "./blinker";
-import { getEnv, getMemosURL } from "./tauri";
+import { getEnv, getMemosURL, openLogWindow } from "./tauri";
 
 async function addManualRedirectButton() {
     document.getElementById("manual-redirect-btn")?.addEventListener("click", () => {
         window.location.replace("/");
     });
+    document.getElementById("view-logs-btn")?.addEventListener("click", () => {
+        openLogWindow();
+    });
 
     const urlElement = document.querySelector<HTMLParagraphElement>("#url");
     if (urlElement) {
@@ -69,9 +72,11 @@ async function redirectOnResponse() {
                 waitingElement.innerHTML = "Something went wrong 😢";
             }
 
-            const button = document.getElementById("manual-redirect-btn");
-            if (button instanceof Element) {
-                button.setAttribute("style", "visibility: visible;");
+            for (const id of ["manual-redirect-btn", "view-logs-btn"]) {
+                const button = document.getElementById(id);
+                if (button instanceof Element) {
+                    button.setAttribute("style", "visibility: visible;");
+                }
             }
 
             return;
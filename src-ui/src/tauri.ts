@@ -5,6 +5,7 @@
  *
  */
 
+import { listen } from "@tauri-apps/api/event";
 import { invoke as TauriInvoke } from "@tauri-apps/api/tauri";
 
 const browserError = new Error("Not running in Tauri!");
@@ -23,3 +24,203 @@ export function getMemosURL(): Promise<string> {
 export function getEnv(name: string): Promise<string> {
     return invoke("get_env", { name: name });
 }
+
+export interface OrphanedAsset {
+    path: string;
+    size: number;
+}
+
+export interface MissingAsset {
+    id: number;
+    filename: string;
+    reference: string;
+    path: string;
+    size: number;
+}
+
+export interface AssetReport {
+    orphaned: OrphanedAsset[];
+    missing: MissingAsset[];
+}
+
+/**
+ * Scan Memos assets for orphaned and missing files.
+ *
+ * Orphaned files are moved to a quarantine folder if `quarantine` is true.
+ */
+export function scanAssets(quarantine: boolean): Promise<AssetReport> {
+    return invoke("scan_assets", { quarantine: quarantine });
+}
+
+/**
+ * Relocate Memos data directory and restart Memospot.
+ *
+ * Rejects with an error message if the relocation fails.
+ */
+export function relocateMemosData(target: string): Promise<void> {
+    return invoke("relocate_memos_data", { target: target });
+}
+
+/**
+ * Switch to a saved workspace, restarting the Memos server with its settings.
+ */
+export function switchWorkspace(name: string): Promise<void> {
+    return invoke("switch_workspace", { name: name });
+}
+
+/**
+ * Switch the active remote server profile.
+ *
+ * Use "local" to switch back to the local Memos server.
+ */
+export function switchRemoteProfile(name: string): Promise<void> {
+    return invoke("switch_remote_profile", { name: name });
+}
+
+/**
+ * Save a secret in the system keyring.
+ *
+ * Configuration values can then refer to it as `secret:NAME`.
+ */
+export function setSecret(name: string, value: string): Promise<void> {
+    return invoke("set_secret", { name: name, value: value });
+}
+
+export interface ConfigOrigin {
+    /** Dotted setting key, such as `memos.mode`. */
+    key: string;
+    /** Effective value, as JSON. Secrets are redacted. */
+    value: string;
+    /** Default value, as JSON, if any. */
+    default: string | null;
+    /** Where the value comes from, such as `default` or a file. */
+    source: string;
+    /** Profile section the value comes from, if any. */
+    profile: string | null;
+}
+
+/**
+ * List every effective setting, with where its value comes from.
+ *
+ * @param changedOnly Leave out settings with their default value.
+ */
+export function getConfigOrigins(changedOnly = false): Promise<ConfigOrigin[]> {
+    return invoke("get_config_origins", { changedOnly: changedOnly });
+}
+
+/**
+ * Export the settings to a portable bundle, asking where to save it.
+ *
+ * Paths under the home directory are written as `~/...`.
+ * Resolves with the bundle path, or null if cancelled.
+ */
+export function exportSettings(): Promise<string | null> {
+    return invoke("export_settings");
+}
+
+/**
+ * Import the settings from a bundle, asking which one, and restart the app.
+ *
+ * The current configuration file is backed up first.
+ */
+export function importSettings(): Promise<void> {
+    return invoke("import_settings");
+}
+
+export interface LogFile {
+    /** File name, such as `memospot.log` or `memospot.log.1.gz`. */
+    name: string;
+    /** File size, in bytes. */
+    size: number;
+    /** Whether this is a rotated, compressed file. */
+    rotated: boolean;
+}
+
+export interface LogFiles {
+    /** Whether logging is enabled. */
+    enabled: boolean;
+    /** Path of the current log file. */
+    path: string;
+    /** Current log file, followed by the rotated ones, newest first. */
+    files: LogFile[];
+}
+
+export interface LogEntry {
+    timestamp: string;
+    /** Level, such as `INFO`. Empty if the line couldn't be parsed. */
+    level: string;
+    /** Target, such as `memospot::init` or `memos`. */
+    target: string;
+    message: string;
+    /** Structured fields, from JSON log lines. */
+    fields: Record<string, unknown>;
+}
+
+/**
+ * List the current and rotated log files.
+ */
+export function listLogFiles(): Promise<LogFiles> {
+    return invoke("list_log_files");
+}
+
+/**
+ * Read the last records of a log file.
+ *
+ * @param file A file name from `listLogFiles`, or null for the current log file.
+ * @param lines Number of records to return.
+ * @param level Minimum level, such as `warn`.
+ * @param target Target, also matching its modules.
+ */
+export function tailLog(
+    file: string | null,
+    lines: number,
+    level: string | null = null,
+    target: string | null = null
+): Promise<LogEntry[]> {
+    return invoke("tail_log", {
+        file: file,
+        lines: lines,
+        level: level,
+        target: target
+    });
+}
+
+/**
+ * Open the log window.
+ */
+export function openLogWindow(): Promise<void> {
+    return invoke("open_log_window");
+}
+
+/**
+ * Create a diagnostic support bundle, and return its path.
+ *
+ * The bundle holds the redacted configuration, recent logs, versions, paths and
+ * database statistics. Note contents are never included.
+ *
+ * @param path Where to save the bundle. Defaults to the downloads directory.
+ */
+export function createSupportBundle(path: string | null = null): Promise<string> {
+    return invoke("create_support_bundle", { path: path });
+}
+
+export interface ConfigReloaded {
+    /** Keys applied right away. */
+    applied: string[];
+    /** Keys that take effect on the next launch. */
+    restart_required: string[];
+}
+
+/**
+ * Listen for configuration file changes made while the app is running.
+ *
+ * Returns a function that stops listening.
+ */
+export async function onConfigReloaded(
+    handler: (payload: ConfigReloaded) => void,
+): Promise<() => void> {
+    if (!window.__TAURI__) {
+        return () => {};
+    }
+    return listen<ConfigReloaded>("memospot://config-reloaded", (event) => handler(event.payload));
+}
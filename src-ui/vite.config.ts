@@ -1,3 +1,4 @@
+import { fileURLToPath } from "node:url";
 import { defineConfig } from "vite";
 import htmlMinifier from "vite-plugin-html-minifier";
 
@@ -8,7 +9,13 @@ export default defineConfig({
         outDir: "../dist-ui",
         target: ["es2021", "chrome97", "safari13"],
         minify: !process.env.TAURI_DEBUG ? "terser" : false,
-        sourcemap: !!process.env.TAURI_DEBUG
+        sourcemap: !!process.env.TAURI_DEBUG,
+        rollupOptions: {
+            input: {
+                main: fileURLToPath(new URL("index.html", import.meta.url)),
+                logs: fileURLToPath(new URL("logs.html", import.meta.url))
+            }
+        }
     },
     // prevent vite from obscuring rust errors
     clearScreen: false,